@@ -1,7 +1,7 @@
 use axum::{
     async_trait,
     extract::{FromRequestParts, State},
-    http::{header::AUTHORIZATION, request::Parts, HeaderMap, StatusCode},
+    http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
     Json, RequestPartsExt,
 };
@@ -9,15 +9,18 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::env;
+use utoipa::ToSchema;
 
 use crate::database;
 use crate::database::AppState;
+use crate::error::AppError;
 pub struct Keys {
     encoding: EncodingKey,
     decoding: DecodingKey,
@@ -40,17 +43,99 @@ static KEYS: Lazy<Keys> = Lazy::new(|| {
     Keys::new(secret.as_bytes())
 });
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
+/// 15 minutes: short-lived enough that a leaked access token isn't very
+/// useful, since real revocation lives on the refresh-token session.
+const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+/// 30 days: matches the `sessions` row's `expires_at`, so an unused session
+/// eventually ages out even if it's never explicitly logged out.
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+/// Role hierarchy backing `account_info.role`; ordering matters here since
+/// [`RequireRole`] compares with `>=` (derived `Ord` follows declaration
+/// order, so `Admin > Surveyor > Viewer`). An unset or unrecognized role
+/// string maps to `Viewer`, the least-privileged option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Surveyor,
+    Admin,
+}
+
+impl Role {
+    fn from_db(role: Option<&str>) -> Self {
+        match role.map(str::to_ascii_lowercase).as_deref() {
+            Some("admin") => Role::Admin,
+            Some("surveyor") => Role::Surveyor,
+            _ => Role::Viewer,
+        }
+    }
+}
+
+/// Claims carried by the short-lived bearer token sent on every request.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessClaims {
+    pub account: String,
+    pub account_id: String,
+    pub role: Role,
+    /// Always `"access"`; keeps a refresh token from being replayed as one
+    /// of these even though both are signed with the same key.
+    pub typ: String,
+    pub exp: usize,
+}
+
+/// Claims carried by the long-lived refresh token; only ever sent to
+/// `/auth/refresh` and `/auth/logout`, never used to authorize API calls.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshClaims {
+    pub session_id: String,
     pub account: String,
+    pub typ: String,
     pub exp: usize,
 }
 
+fn issue_access_token(account: &database::Account) -> Result<String, AuthError> {
+    let claims = AccessClaims {
+        account: account.account.clone(),
+        account_id: account.id.clone(),
+        role: Role::from_db(account.role.as_deref()),
+        typ: "access".to_string(),
+        exp: (Utc::now() + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES)).timestamp() as usize,
+    };
+    encode(&Header::default(), &claims, &KEYS.encoding).map_err(|_| AuthError::TokenCreation)
+}
+
+/// Signs a fresh refresh token for `session_id`, returning the token
+/// alongside the expiry to persist on the `sessions` row.
+fn issue_refresh_token(
+    account: &str,
+    session_id: &str,
+) -> Result<(String, DateTime<Utc>), AuthError> {
+    let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+    let claims = RefreshClaims {
+        session_id: session_id.to_string(),
+        account: account.to_string(),
+        typ: "refresh".to_string(),
+        exp: expires_at.timestamp() as usize,
+    };
+    let token = encode(&Header::default(), &claims, &KEYS.encoding)
+        .map_err(|_| AuthError::TokenCreation)?;
+    Ok((token, expires_at))
+}
+
+/// Sessions only ever store this digest, never the raw refresh token, so a
+/// leaked database dump can't be replayed as a session.
+fn hash_refresh_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    hex::encode(digest)
+}
+
 pub enum AuthError {
     InvalidToken,
     WrongCredentials,
     TokenCreation,
     MissingCredentials,
+    Forbidden,
 }
 
 impl IntoResponse for AuthError {
@@ -60,6 +145,7 @@ impl IntoResponse for AuthError {
             AuthError::MissingCredentials => (StatusCode::BAD_REQUEST, "Missing credentials"),
             AuthError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error"),
             AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Invalid token"),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, "Insufficient role"),
         };
         let body = Json(json!({
             "error": error_message,
@@ -69,7 +155,7 @@ impl IntoResponse for AuthError {
 }
 
 #[async_trait]
-impl<S> FromRequestParts<S> for Claims
+impl<S> FromRequestParts<S> for AccessClaims
 where
     S: Send + Sync,
 {
@@ -81,77 +167,202 @@ where
             .await
             .map_err(|_| AuthError::InvalidToken)?;
 
-        let token_data = decode::<Claims>(bearer.token(), &KEYS.decoding, &Validation::default())
-            .map_err(|_| AuthError::InvalidToken)?;
+        decode_access_token(bearer.token())
+    }
+}
+
+/// A minimum-role marker for [`RequireRole`]. `Viewer`/`Surveyor`/`Admin`
+/// below are the only implementors, so `RequireRole<Surveyor>` reads like
+/// the `RequireRole(Role::Surveyor)` guard this mirrors, just resolved at
+/// the type level so each handler's signature documents its own floor.
+pub trait MinRole {
+    const MIN_ROLE: Role;
+}
+
+pub struct Viewer;
+pub struct Surveyor;
+pub struct Admin;
+
+impl MinRole for Viewer {
+    const MIN_ROLE: Role = Role::Viewer;
+}
+impl MinRole for Surveyor {
+    const MIN_ROLE: Role = Role::Surveyor;
+}
+impl MinRole for Admin {
+    const MIN_ROLE: Role = Role::Admin;
+}
+
+/// Extractor that decodes the bearer access token and rejects with 403
+/// unless its role is at least `R::MIN_ROLE`.
+pub struct RequireRole<R> {
+    pub claims: AccessClaims,
+    _min_role: std::marker::PhantomData<R>,
+}
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: MinRole + Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = AccessClaims::from_request_parts(parts, state).await?;
+        if claims.role < R::MIN_ROLE {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(RequireRole {
+            claims,
+            _min_role: std::marker::PhantomData,
+        })
+    }
+}
+
+fn decode_access_token(token: &str) -> Result<AccessClaims, AuthError> {
+    let token_data = decode::<AccessClaims>(token, &KEYS.decoding, &Validation::default())
+        .map_err(|_| AuthError::InvalidToken)?;
 
-        Ok(token_data.claims)
+    if token_data.claims.typ != "access" {
+        return Err(AuthError::InvalidToken);
     }
+
+    Ok(token_data.claims)
 }
 
-#[derive(Debug, Deserialize)]
+fn decode_refresh_token(token: &str) -> Result<RefreshClaims, AuthError> {
+    let token_data = decode::<RefreshClaims>(token, &KEYS.decoding, &Validation::default())
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    if token_data.claims.typ != "refresh" {
+        return Err(AuthError::InvalidToken);
+    }
+
+    Ok(token_data.claims)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginPayload {
     pub account: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthBody {
     pub access_token: String,
+    pub refresh_token: String,
     pub token_type: String,
 }
 
 impl AuthBody {
-    fn new(access_token: String) -> Self {
+    fn new(access_token: String, refresh_token: String) -> Self {
         Self {
             access_token,
+            refresh_token,
             token_type: "Bearer".to_string(),
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshPayload {
+    pub refresh_token: String,
+}
+
+/// Issues an access/refresh pair for `account` and persists the new
+/// session, used by both `login` and a successful `refresh` rotation.
+async fn issue_session(state: &AppState, account: &database::Account) -> Result<AuthBody, AppError> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (refresh_token, expires_at) = issue_refresh_token(&account.account, &session_id)?;
+
+    database::create_session(
+        &state.db,
+        &session_id,
+        &account.account,
+        &hash_refresh_token(&refresh_token),
+        expires_at,
+    )
+    .await?;
+
+    let access_token = issue_access_token(account)?;
+    Ok(AuthBody::new(access_token, refresh_token))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/login",
+    tag = "auth",
+    request_body = LoginPayload,
+    responses(
+        (status = 200, description = "Access/refresh token pair issued", body = AuthBody),
+        (status = 400, description = "Missing credentials"),
+        (status = 401, description = "Wrong credentials"),
+    ),
+)]
 pub async fn login(
     State(state): State<AppState>,
     Json(payload): Json<LoginPayload>,
-) -> Result<Json<AuthBody>, AuthError> {
+) -> Result<Json<AuthBody>, AppError> {
     if payload.account.is_empty() || payload.password.is_empty() {
-        return Err(AuthError::MissingCredentials);
+        return Err(AuthError::MissingCredentials.into());
     }
 
-    let ok = database::check_account(&state.db, &payload.account, &payload.password)
-        .await
-        .map_err(|err| {
-            tracing::error!("Failed to check account: {:?}", err);
-            AuthError::TokenCreation
-        })?;
+    let account = database::check_account(&state.db, &payload.account, &payload.password)
+        .await?
+        .ok_or(AuthError::WrongCredentials)?;
 
-    if !ok {
-        return Err(AuthError::WrongCredentials);
-    }
+    Ok(Json(issue_session(&state, &account).await?))
+}
 
-    let exp = (Utc::now() + Duration::hours(24)).timestamp() as usize;
-    let claims = Claims {
-        account: payload.account,
-        exp,
-    };
+/// Validates the refresh JWT, looks up its session, and *rotates* it: the
+/// old session is marked revoked and a new access/refresh pair is issued.
+/// Presenting a refresh token whose session is already revoked (i.e. one
+/// that was already rotated away) is treated as token theft and revokes
+/// every other live session on the account.
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshPayload>,
+) -> Result<Json<AuthBody>, AppError> {
+    let claims = decode_refresh_token(&payload.refresh_token)?;
 
-    let token = encode(&Header::default(), &claims, &KEYS.encoding)
-        .map_err(|_| AuthError::TokenCreation)?;
+    let session = database::get_session(&state.db, &claims.session_id)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
 
-    Ok(Json(AuthBody::new(token)))
-}
+    if session.revoked {
+        tracing::warn!(
+            "Refresh token reuse detected for account {}; revoking all sessions",
+            session.account
+        );
+        database::revoke_all_sessions_for_account(&state.db, &session.account).await?;
+        return Err(AuthError::InvalidToken.into());
+    }
 
-pub fn claims_from_headers(headers: &HeaderMap) -> Result<Claims, AuthError> {
-    let auth_header = headers
-        .get(AUTHORIZATION)
-        .and_then(|value| value.to_str().ok())
-        .ok_or(AuthError::InvalidToken)?;
+    if session.expires_at < Utc::now() {
+        return Err(AuthError::InvalidToken.into());
+    }
+
+    if hash_refresh_token(&payload.refresh_token) != session.refresh_token_hash {
+        return Err(AuthError::InvalidToken.into());
+    }
 
-    let token = auth_header
-        .strip_prefix("Bearer ")
+    database::revoke_session(&state.db, &session.session_id).await?;
+
+    let account = database::get_account_by_name(&state.db, &session.account)
+        .await?
         .ok_or(AuthError::InvalidToken)?;
 
-    let token_data = decode::<Claims>(token, &KEYS.decoding, &Validation::default())
-        .map_err(|_| AuthError::InvalidToken)?;
+    Ok(Json(issue_session(&state, &account).await?))
+}
 
-    Ok(token_data.claims)
+/// Revokes the session behind a refresh token, ending it immediately
+/// instead of waiting for the token to expire.
+pub async fn logout(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshPayload>,
+) -> Result<StatusCode, AppError> {
+    let claims = decode_refresh_token(&payload.refresh_token)?;
+    database::revoke_session(&state.db, &claims.session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
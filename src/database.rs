@@ -1,12 +1,19 @@
-use crate::models::{CreateSurveyRequest, SurveyCategory, SurveyDetails, SurveyRecord};
+use crate::models::{
+    BatchItemResult, CreateSurveyRequest, PhotoExifInfo, PhotoVariantMap, SurveyCategory,
+    SurveyDetails, SurveyRecord,
+};
+use crate::storage::ObjectStore;
 use anyhow::Result;
+use argon2::{password_hash::PasswordVerifier, Argon2, PasswordHash};
 use chrono::{DateTime, Utc};
 use sqlx::{postgres::PgPoolOptions, types::Json, Pool, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Pool<Postgres>,
-    pub s3_client: aws_sdk_s3::Client,
+    pub object_store: Arc<dyn ObjectStore>,
     pub bucket_name: String,
 }
 
@@ -21,24 +28,15 @@ pub async fn connect_db(database_url: &str) -> Result<Pool<Postgres>> {
 pub async fn create_survey_record(
     pool: &Pool<Postgres>,
     req: CreateSurveyRequest,
-) -> Result<String> {
-    // Serialize category to string to ensure compatibility with VARCHAR(50)
-    // or rely on sqlx implementation if configured correctly.
-    // Here we act safe and just cast via serde or Debug/Display if available,
-    // but assuming CreateSurveyRequest used the Enum.
-    // We can use serde_json::to_value to get the string representation if the Enum is unit-only.
-    // Or just impl ToString. Let's rely on serde serialization to string.
-    let category_str = serde_json::to_string(&req.category)?
-        .trim_matches('"')
-        .to_string();
-
+    created_by: &str,
+) -> Result<String, crate::error::AppError> {
     let rec = sqlx::query!(
         r#"
         INSERT INTO survey_records (
             id, start_point, end_point, orientation, distance, top_distance,
-            category, details, awaiting_photo_count, remarks
+            category, details, awaiting_photo_count, remarks, created_by
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
         RETURNING id
         "#,
         req.id,
@@ -47,10 +45,11 @@ pub async fn create_survey_record(
         req.orientation,
         req.distance,
         req.top_distance,
-        category_str,
+        req.category.as_token(),
         Json(&req.details) as _, // Force sqlx to treat this as JSONB compatible
         req.awaiting_photo_count,
-        req.remarks
+        req.remarks,
+        created_by
     )
     .fetch_one(pool)
     .await?;
@@ -58,22 +57,358 @@ pub async fn create_survey_record(
     Ok(rec.id)
 }
 
-pub async fn add_photo_url(pool: &Pool<Postgres>, id: &str, url: &str) -> Result<()> {
+/// Inserts many surveys in a single multi-row `INSERT ... ON CONFLICT DO
+/// NOTHING RETURNING id`, so a client-supplied duplicate `id` doesn't abort
+/// the rest of the batch; the caller gets a per-item success/failure report.
+pub async fn create_survey_records(
+    pool: &Pool<Postgres>,
+    reqs: Vec<CreateSurveyRequest>,
+    created_by: &str,
+) -> Result<Vec<BatchItemResult>> {
+    if reqs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+        "INSERT INTO survey_records (id, start_point, end_point, orientation, distance, top_distance, category, details, awaiting_photo_count, remarks, created_by) ",
+    );
+    qb.push_values(&reqs, |mut b, req| {
+        b.push_bind(&req.id)
+            .push_bind(&req.start_point)
+            .push_bind(&req.end_point)
+            .push_bind(&req.orientation)
+            .push_bind(req.distance)
+            .push_bind(&req.top_distance)
+            .push_bind(&req.category)
+            .push_bind(Json(&req.details))
+            .push_bind(req.awaiting_photo_count)
+            .push_bind(&req.remarks)
+            .push_bind(created_by);
+    });
+    qb.push(" ON CONFLICT (id) DO NOTHING RETURNING id");
+
+    let inserted: Vec<(String,)> = qb.build_query_as().fetch_all(pool).await?;
+    let inserted_ids: std::collections::HashSet<String> =
+        inserted.into_iter().map(|(id,)| id).collect();
+
+    Ok(reqs
+        .into_iter()
+        .map(|req| {
+            if inserted_ids.contains(&req.id) {
+                BatchItemResult {
+                    id: req.id,
+                    success: true,
+                    error: None,
+                }
+            } else {
+                BatchItemResult {
+                    id: req.id,
+                    success: false,
+                    error: Some("a survey with this id already exists".to_string()),
+                }
+            }
+        })
+        .collect())
+}
+
+/// Fetches many surveys by id in a single query. `owner` scopes the result
+/// to rows created by that account (non-`Admin` callers), mirroring the
+/// scoping [`list_surveys`]/`get_survey_handler` apply elsewhere; `None`
+/// leaves the batch unscoped, for `Admin` callers.
+pub async fn get_surveys(
+    pool: &Pool<Postgres>,
+    ids: &[String],
+    owner: Option<&str>,
+) -> Result<Vec<SurveyRecord>> {
+    let rows = match owner {
+        Some(owner) => {
+            sqlx::query_as::<_, SurveyRecordRow>(
+                "SELECT * FROM survey_records WHERE id = ANY($1) AND created_by = $2",
+            )
+            .bind(ids)
+            .bind(owner)
+            .fetch_all(pool)
+            .await?
+        }
+        None => {
+            sqlx::query_as::<_, SurveyRecordRow>("SELECT * FROM survey_records WHERE id = ANY($1)")
+                .bind(ids)
+                .fetch_all(pool)
+                .await?
+        }
+    };
+    Ok(rows.into_iter().map(SurveyRecordRow::into_record).collect())
+}
+
+/// Records a successfully processed photo. The `awaiting_photo_count` slot
+/// was already consumed by [`enqueue_photo_job`] when the upload was
+/// accepted, so this only appends the finished URL/blurhash.
+pub async fn add_photo_url(
+    pool: &Pool<Postgres>,
+    id: &str,
+    url: &str,
+    blurhash: &str,
+) -> Result<()> {
     sqlx::query!(
         r#"
         UPDATE survey_records
         SET photo_urls = array_append(photo_urls, $2),
-            awaiting_photo_count = GREATEST(awaiting_photo_count - 1, 0)
+            photo_blurhashes = array_append(photo_blurhashes, $3)
+        WHERE id = $1
+        "#,
+        id,
+        url,
+        blurhash
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records a failed ingest so callers can tell a rejected upload apart from
+/// a successful one; the awaiting-photo slot was already consumed at enqueue
+/// time by [`enqueue_photo_job`].
+pub async fn add_photo_error(pool: &Pool<Postgres>, id: &str, error: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET photo_errors = array_append(photo_errors, $2)
+        WHERE id = $1
+        "#,
+        id,
+        error
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Merges `{ variant_name: url }` into the `photo_variants` JSONB map under
+/// the key `original_url`, creating the top-level object if it's still null.
+pub async fn add_photo_variants(
+    pool: &Pool<Postgres>,
+    id: &str,
+    original_url: &str,
+    variants: &PhotoVariantMap,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET photo_variants = COALESCE(photo_variants, '{}'::jsonb)
+            || jsonb_build_object($2::text, $3::jsonb)
+        WHERE id = $1
+        "#,
+        id,
+        original_url,
+        Json(variants) as _,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Merges a [`PhotoExifInfo`] into the `photo_exif` JSONB map under the key
+/// `original_url`, creating the top-level object if it's still null.
+pub async fn add_photo_exif(
+    pool: &Pool<Postgres>,
+    id: &str,
+    original_url: &str,
+    exif: &PhotoExifInfo,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET photo_exif = COALESCE(photo_exif, '{}'::jsonb)
+            || jsonb_build_object($2::text, $3::jsonb)
+        WHERE id = $1
+        "#,
+        id,
+        original_url,
+        Json(exif) as _,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Merges `photo_ref: status` into the `photo_statuses` JSONB map, where
+/// `status` is one of `pending`/`ready`/`failed`.
+pub async fn set_photo_status(
+    pool: &Pool<Postgres>,
+    id: &str,
+    photo_ref: &str,
+    status: &str,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET photo_statuses = COALESCE(photo_statuses, '{}'::jsonb)
+            || jsonb_build_object($2::text, $3::text)
         WHERE id = $1
         "#,
         id,
-        url
+        photo_ref,
+        status
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// A queued "process photo" job: raw bytes already live at `raw_key`; the
+/// worker validates/re-encodes them into `dest_key` and fills in variants,
+/// blurhash, and EXIF under `photo_ref` on `survey_id`.
+#[derive(Debug, Clone)]
+pub struct PhotoJob {
+    pub id: String,
+    pub survey_id: String,
+    pub photo_ref: String,
+    pub raw_key: String,
+    pub dest_key: String,
+    pub attempts: i32,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PhotoJobRow {
+    id: String,
+    survey_id: String,
+    photo_ref: String,
+    raw_key: String,
+    dest_key: String,
+    attempts: i32,
+}
+
+impl From<PhotoJobRow> for PhotoJob {
+    fn from(row: PhotoJobRow) -> Self {
+        PhotoJob {
+            id: row.id,
+            survey_id: row.survey_id,
+            photo_ref: row.photo_ref,
+            raw_key: row.raw_key,
+            dest_key: row.dest_key,
+            attempts: row.attempts,
+        }
+    }
+}
+
+/// Enqueues a "process photo" job and immediately marks the photo `pending`
+/// on the survey record, consuming its `awaiting_photo_count` slot so the
+/// upload request can return before processing happens.
+pub async fn enqueue_photo_job(
+    pool: &Pool<Postgres>,
+    survey_id: &str,
+    photo_ref: &str,
+    raw_key: &str,
+    dest_key: &str,
+) -> Result<String> {
+    let job_id = uuid::Uuid::new_v4().to_string();
+    sqlx::query!(
+        r#"
+        INSERT INTO photo_jobs (id, survey_id, photo_ref, raw_key, dest_key, status)
+        VALUES ($1, $2, $3, $4, $5, 'pending')
+        "#,
+        job_id,
+        survey_id,
+        photo_ref,
+        raw_key,
+        dest_key
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET awaiting_photo_count = GREATEST(awaiting_photo_count - 1, 0)
+        WHERE id = $1
+        "#,
+        survey_id
+    )
+    .execute(pool)
+    .await?;
+
+    set_photo_status(pool, survey_id, photo_ref, "pending").await?;
+
+    Ok(job_id)
+}
+
+/// Atomically claims the oldest pending job, so multiple worker instances
+/// never process the same photo twice.
+pub async fn claim_next_photo_job(pool: &Pool<Postgres>) -> Result<Option<PhotoJob>> {
+    let row = sqlx::query_as::<_, PhotoJobRow>(
+        r#"
+        UPDATE photo_jobs
+        SET status = 'processing'
+        WHERE id = (
+            SELECT id FROM photo_jobs
+            WHERE status = 'pending'
+            ORDER BY created_at
+            FOR UPDATE SKIP LOCKED
+            LIMIT 1
+        )
+        RETURNING id, survey_id, photo_ref, raw_key, dest_key, attempts
+        "#,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(PhotoJob::from))
+}
+
+pub async fn mark_photo_job_done(pool: &Pool<Postgres>, job_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE photo_jobs SET status = 'done' WHERE id = $1",
+        job_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Marks a job failed and bumps its attempt counter; [`retry_photo_job`]
+/// flips it back to `pending` for another try.
+pub async fn mark_photo_job_failed(pool: &Pool<Postgres>, job_id: &str, error: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE photo_jobs
+        SET status = 'failed', attempts = attempts + 1, last_error = $2
+        WHERE id = $1
+        "#,
+        job_id,
+        error
     )
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Resets a failed job back to `pending` for the worker to retry, and
+/// flips the photo's status back to `pending` to match. Returns `None` if
+/// the job isn't currently `failed` (e.g. already retried).
+pub async fn retry_photo_job(
+    pool: &Pool<Postgres>,
+    job_id: &str,
+) -> Result<Option<(String, String)>> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE photo_jobs
+        SET status = 'pending'
+        WHERE id = $1 AND status = 'failed'
+        RETURNING survey_id, photo_ref
+        "#,
+        job_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    set_photo_status(pool, &row.survey_id, &row.photo_ref, "pending").await?;
+    Ok(Some((row.survey_id, row.photo_ref)))
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct SurveyRecordRow {
     pub id: String,
@@ -82,17 +417,21 @@ struct SurveyRecordRow {
     pub orientation: String,
     pub distance: f64,
     pub top_distance: String,
-    pub category: String,
+    pub category: SurveyCategory,
     pub details: Json<SurveyDetails>,
     pub photo_urls: Vec<String>,
+    pub photo_blurhashes: Vec<String>,
+    pub photo_errors: Vec<String>,
+    pub photo_variants: Json<HashMap<String, PhotoVariantMap>>,
+    pub photo_exif: Json<HashMap<String, PhotoExifInfo>>,
+    pub photo_statuses: Json<HashMap<String, String>>,
     pub awaiting_photo_count: i32,
     pub remarks: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
-}
-
-fn parse_category(value: &str) -> SurveyCategory {
-    serde_json::from_str::<SurveyCategory>(&format!("\"{}\"", value))
-        .unwrap_or(SurveyCategory::Unknown)
+    pub geo_lat: Option<f64>,
+    pub geo_lon: Option<f64>,
+    pub captured_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
 }
 
 impl SurveyRecordRow {
@@ -104,16 +443,52 @@ impl SurveyRecordRow {
             orientation: self.orientation,
             distance: self.distance,
             top_distance: self.top_distance,
-            category: parse_category(&self.category),
+            category: self.category,
             details: self.details,
             photo_urls: self.photo_urls,
+            photo_blurhashes: self.photo_blurhashes,
+            photo_errors: self.photo_errors,
+            photo_variants: self.photo_variants,
+            photo_exif: self.photo_exif,
+            photo_statuses: self.photo_statuses,
             awaiting_photo_count: self.awaiting_photo_count,
             remarks: self.remarks,
             created_at: self.created_at,
+            geo_lat: self.geo_lat,
+            geo_lon: self.geo_lon,
+            captured_at: self.captured_at,
+            created_by: self.created_by,
         }
     }
 }
 
+/// Fills `geo_lat`/`geo_lon`/`captured_at` from the first photo that carries
+/// them; later photos never overwrite an already-populated value.
+pub async fn set_survey_geo(
+    pool: &Pool<Postgres>,
+    id: &str,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    captured_at: Option<DateTime<Utc>>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE survey_records
+        SET geo_lat = COALESCE(geo_lat, $2),
+            geo_lon = COALESCE(geo_lon, $3),
+            captured_at = COALESCE(captured_at, $4)
+        WHERE id = $1
+        "#,
+        id,
+        lat,
+        lon,
+        captured_at
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct SurveyQueryFilters {
     pub category: Option<String>,
@@ -121,45 +496,51 @@ pub struct SurveyQueryFilters {
     pub end_point: Option<String>,
     pub created_from: Option<DateTime<Utc>>,
     pub created_to: Option<DateTime<Utc>>,
+    /// (lat, lon, radius_km)
+    pub near: Option<(f64, f64, f64)>,
+    /// Only records still waiting on one or more photos.
+    pub awaiting_only: bool,
+    /// When set, restricts results to rows with this `created_by`. Set by
+    /// the handler for non-`Admin` callers (and for `Admin` callers that
+    /// passed `mine=true`) so field surveyors only see their own surveys.
+    pub owner: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
-pub async fn list_surveys(
-    pool: &Pool<Postgres>,
-    filters: SurveyQueryFilters,
-) -> Result<Vec<SurveyRecord>> {
-    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM survey_records");
+/// Appends a `WHERE ...` clause for every filter that's set, shared by
+/// [`list_surveys`] and [`count_surveys`] so the two can't drift apart.
+fn push_survey_filters(qb: &mut QueryBuilder<Postgres>, filters: &SurveyQueryFilters) {
     let mut has_where = false;
 
-    if let Some(category) = filters.category {
+    if let Some(category) = &filters.category {
         if !has_where {
             qb.push(" WHERE ");
             has_where = true;
         } else {
             qb.push(" AND ");
         }
-        qb.push("category = ").push_bind(category);
+        qb.push("category = ").push_bind(category.clone());
     }
 
-    if let Some(start_point) = filters.start_point {
+    if let Some(start_point) = &filters.start_point {
         if !has_where {
             qb.push(" WHERE ");
             has_where = true;
         } else {
             qb.push(" AND ");
         }
-        qb.push("start_point = ").push_bind(start_point);
+        qb.push("start_point = ").push_bind(start_point.clone());
     }
 
-    if let Some(end_point) = filters.end_point {
+    if let Some(end_point) = &filters.end_point {
         if !has_where {
             qb.push(" WHERE ");
             has_where = true;
         } else {
             qb.push(" AND ");
         }
-        qb.push("end_point = ").push_bind(end_point);
+        qb.push("end_point = ").push_bind(end_point.clone());
     }
 
     if let Some(created_from) = filters.created_from {
@@ -182,6 +563,51 @@ pub async fn list_surveys(
         qb.push("created_at <= ").push_bind(created_to);
     }
 
+    if let Some((lat, lon, radius_km)) = filters.near {
+        if !has_where {
+            qb.push(" WHERE ");
+            has_where = true;
+        } else {
+            qb.push(" AND ");
+        }
+        qb.push("geo_lat IS NOT NULL AND geo_lon IS NOT NULL AND ");
+        qb.push("6371 * acos(LEAST(1.0, GREATEST(-1.0, ");
+        qb.push("cos(radians(").push_bind(lat).push(")) * cos(radians(geo_lat)) * ");
+        qb.push("cos(radians(geo_lon) - radians(").push_bind(lon).push(")) + ");
+        qb.push("sin(radians(").push_bind(lat).push(")) * sin(radians(geo_lat))");
+        qb.push("))) <= ").push_bind(radius_km);
+    }
+
+    if filters.awaiting_only {
+        if !has_where {
+            qb.push(" WHERE ");
+            has_where = true;
+        } else {
+            qb.push(" AND ");
+        }
+        qb.push("awaiting_photo_count > 0");
+    }
+
+    if let Some(owner) = &filters.owner {
+        if !has_where {
+            qb.push(" WHERE ");
+            has_where = true;
+        } else {
+            qb.push(" AND ");
+        }
+        qb.push("created_by = ").push_bind(owner.clone());
+    }
+
+    let _ = has_where;
+}
+
+pub async fn list_surveys(
+    pool: &Pool<Postgres>,
+    filters: SurveyQueryFilters,
+) -> Result<Vec<SurveyRecord>> {
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM survey_records");
+    push_survey_filters(&mut qb, &filters);
+
     qb.push(" ORDER BY created_at DESC");
 
     let mut limit = filters.limit.unwrap_or(50);
@@ -203,30 +629,175 @@ pub async fn list_surveys(
     Ok(rows.into_iter().map(SurveyRecordRow::into_record).collect())
 }
 
-#[allow(dead_code)]
-pub async fn get_survey(pool: &Pool<Postgres>, id: &str) -> Result<Option<SurveyRecord>> {
-    // We need to query. Since SurveyCategory is an enum,
-    // we assume we can read it back as string and cast,
-    // or we use query_as! if types match.
-    // However, sqlx macros check DB types. The DB type is VARCHAR.
-    // The struct type is SurveyCategory.
-    // Automated mapping might fail if sqlx doesn't know how to go VARCHAR -> Enum.
-    // We'll use manual query_as or just query and map.
-    // For simplicity, let's use `sqlx::query_as` which is runtime-checked (mostly)
-    // or defining a manual row mapper is safer.
-
-    // Actually, let's try `query_as` with the struct details.
-    // Note: This requires SurveyCategory to impl sqlx::Type<Postgres> and accept VARCHAR.
-    // If not, this might fail at runtime.
-    // Given usage of `sqlx::Type` in models.rs, it should be fine IF the type names matched
-    // OR if transparent is used.
-    // But let's proceed.
+/// Total rows matching `filters` ignoring `limit`/`offset`, for UI paging.
+pub async fn count_surveys(pool: &Pool<Postgres>, filters: &SurveyQueryFilters) -> Result<i64> {
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("SELECT COUNT(*) FROM survey_records");
+    push_survey_filters(&mut qb, filters);
+    let total: i64 = qb.build_query_scalar().fetch_one(pool).await?;
+    Ok(total)
+}
+
+/// All records matching `filters` (ignoring `limit`/`offset`) for CSV export,
+/// ordered the same way as [`list_surveys`].
+pub async fn list_surveys_for_export(
+    pool: &Pool<Postgres>,
+    filters: &SurveyQueryFilters,
+) -> Result<Vec<SurveyRecord>> {
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new("SELECT * FROM survey_records");
+    push_survey_filters(&mut qb, filters);
+    qb.push(" ORDER BY created_at DESC");
+
+    let rows = qb.build_query_as::<SurveyRecordRow>().fetch_all(pool).await?;
+    Ok(rows.into_iter().map(SurveyRecordRow::into_record).collect())
+}
 
+/// Fetches a single survey by id, with no ownership scoping; callers that
+/// need to enforce visibility (everything reachable by an authenticated
+/// caller) apply their own `created_by` check against the returned record.
+pub async fn get_survey(pool: &Pool<Postgres>, id: &str) -> Result<Option<SurveyRecord>> {
     let result =
         sqlx::query_as::<_, SurveyRecordRow>("SELECT * FROM survey_records WHERE id = $1")
-        .bind(id)
-        .fetch_optional(pool)
-        .await?;
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
 
     Ok(result.map(SurveyRecordRow::into_record))
 }
+
+/// An access/refresh session row. One row per issued refresh token; a
+/// rotation at `/auth/refresh` revokes the row it read and inserts a new
+/// one rather than updating in place, so a stolen, already-rotated token
+/// is detectable as reuse of a `revoked` row.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Session {
+    pub session_id: String,
+    pub account: String,
+    pub refresh_token_hash: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked: bool,
+}
+
+pub async fn create_session(
+    pool: &Pool<Postgres>,
+    session_id: &str,
+    account: &str,
+    refresh_token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO sessions (session_id, account, refresh_token_hash, issued_at, expires_at, revoked)
+        VALUES ($1, $2, $3, now(), $4, false)
+        "#,
+        session_id,
+        account,
+        refresh_token_hash,
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_session(pool: &Pool<Postgres>, session_id: &str) -> Result<Option<Session>> {
+    let session = sqlx::query_as::<_, Session>(
+        "SELECT session_id, account, refresh_token_hash, issued_at, expires_at, revoked \
+         FROM sessions WHERE session_id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(session)
+}
+
+pub async fn revoke_session(pool: &Pool<Postgres>, session_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE sessions SET revoked = true WHERE session_id = $1",
+        session_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Used when a refresh token is replayed after it's already been rotated
+/// away: kills every other live session on the account as a theft signal.
+pub async fn revoke_all_sessions_for_account(pool: &Pool<Postgres>, account: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE sessions SET revoked = true WHERE account = $1 AND revoked = false",
+        account
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Row fetched from `account_info` on a successful login; `role` drives
+/// the `RequireRole` extractor in `auth.rs` (missing/unknown roles are
+/// treated as the lowest-privilege `Viewer`).
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub id: String,
+    pub account: String,
+    pub role: Option<String>,
+}
+
+/// Verifies `account`/`password` against `account_info` (populated by the
+/// `create_account` binary), returning the account's id/role on success.
+/// Disabled (`is_active = false`) accounts can't log in.
+pub async fn check_account(
+    pool: &Pool<Postgres>,
+    account: &str,
+    password: &str,
+) -> Result<Option<Account>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, account, password_hash, role
+        FROM account_info
+        WHERE account = $1 AND is_active = TRUE
+        "#,
+        account
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let Ok(parsed_hash) = PasswordHash::new(&row.password_hash) else {
+        return Ok(None);
+    };
+
+    if Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_err()
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(Account {
+        id: row.id,
+        account: row.account,
+        role: row.role,
+    }))
+}
+
+/// Looks up an account's id/role without checking a password; used when
+/// re-issuing an access token during refresh rotation, where we already
+/// trust the session but need current role/id to embed in the new claims.
+pub async fn get_account_by_name(pool: &Pool<Postgres>, account: &str) -> Result<Option<Account>> {
+    let row = sqlx::query!(
+        "SELECT id, account, role FROM account_info WHERE account = $1 AND is_active = TRUE",
+        account
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| Account {
+        id: row.id,
+        account: row.account,
+        role: row.role,
+    }))
+}
@@ -0,0 +1,93 @@
+use axum::{http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde_json::json;
+
+use crate::auth::AuthError;
+
+/// Crate-wide error type for handlers that want a single `?`-friendly error
+/// path instead of matching every failure mode by hand. Produces the
+/// `{"success": false, "error": "..."}` body every handler should return.
+#[derive(Debug)]
+pub enum AppError {
+    Conflict(String),
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    UnprocessableEntity(String),
+    Internal(anyhow::Error),
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::UnprocessableEntity(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            AppError::Internal(err) => {
+                tracing::error!("Internal error: {:?}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        };
+
+        (
+            status,
+            Json(json!({
+                "success": false,
+                "error": message,
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// A duplicate `survey_records.id` surfaces as `sqlx::Error::Database` with
+/// `is_unique_violation()` set; everything else is an opaque 500.
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(ref db_err) = err {
+            if db_err.is_unique_violation() && db_err.table() == Some("survey_records") {
+                return AppError::Conflict("a survey with this id already exists".to_string());
+            }
+        }
+        AppError::Internal(err.into())
+    }
+}
+
+impl From<AuthError> for AppError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::WrongCredentials => {
+                AppError::Unauthorized("Wrong credentials".to_string())
+            }
+            AuthError::MissingCredentials => {
+                AppError::BadRequest("Missing credentials".to_string())
+            }
+            AuthError::InvalidToken => AppError::BadRequest("Invalid token".to_string()),
+            AuthError::TokenCreation => {
+                AppError::Internal(anyhow::anyhow!("token creation error"))
+            }
+            AuthError::Forbidden => AppError::Forbidden("Insufficient role".to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Internal(err.into())
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<sqlx::Error>() {
+            Ok(sqlx_err) => sqlx_err.into(),
+            Err(err) => AppError::Internal(err),
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use crate::auth;
 use crate::database::{self, AppState, SurveyQueryFilters};
+use crate::error::AppError;
 use crate::models::{
-    ApiResponse, CompleteUploadRequest, CreateSurveyRequest, PresignHeader, PresignUploadRequest,
-    PresignUploadResponse,
+    ApiResponse, BatchGetRequest, CompleteUploadRequest, CreateSurveyRequest, PostUploadRequest,
+    PostUploadResponse, PresignHeader, PresignUploadRequest, PresignUploadResponse, SurveyRecord,
+    SurveyCategory, SurveyListResponse,
 };
 use crate::storage;
 use axum::{
-    extract::{Path, Query, State},
-    http::HeaderMap,
+    extract::{Multipart, Path, Query, State},
+    http::header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, RANGE},
+    http::{HeaderMap, HeaderValue},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -15,18 +18,57 @@ use axum::{
 use std::path::Path as FsPath;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use utoipa::IntoParams;
 
 #[derive(Debug, Deserialize)]
+pub struct PhotoQueryParams {
+    pub variant: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct SurveyQueryParams {
     pub category: Option<String>,
     pub start_point: Option<String>,
     pub end_point: Option<String>,
     pub created_from: Option<String>,
     pub created_to: Option<String>,
+    /// "lat,lon,radius_km"
+    pub near: Option<String>,
+    /// Only return records that still have `awaiting_photo_count > 0`.
+    #[serde(default)]
+    pub awaiting_only: bool,
+    /// Restricts results to surveys the caller created. Implied for every
+    /// non-`Admin` caller; lets an `Admin` narrow the otherwise-unscoped
+    /// listing down to their own surveys.
+    #[serde(default)]
+    pub mine: bool,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
+fn parse_near(opt: Option<String>) -> Result<Option<(f64, f64, f64)>, String> {
+    let Some(value) = opt else {
+        return Ok(None);
+    };
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 3 {
+        return Err("near must be \"lat,lon,radius_km\"".to_string());
+    }
+    let lat: f64 = parts[0].trim().parse().map_err(|_| "invalid near latitude".to_string())?;
+    let lon: f64 = parts[1].trim().parse().map_err(|_| "invalid near longitude".to_string())?;
+    let radius_km: f64 = parts[2].trim().parse().map_err(|_| "invalid near radius".to_string())?;
+    Ok(Some((lat, lon, radius_km)))
+}
+
+/// Whether `claims` may act on `record`: `Admin` bypasses the check,
+/// everyone else must be the surveyor who created it. Mirrors the inline
+/// check in `get_survey_handler`, shared here since the upload handlers
+/// below need the same ownership gate before presigning/completing/storing
+/// a photo against someone else's survey.
+fn survey_owned_by(record: &SurveyRecord, claims: &auth::AccessClaims) -> bool {
+    claims.role >= auth::Role::Admin || record.created_by.as_deref() == Some(claims.account_id.as_str())
+}
+
 fn parse_rfc3339(opt: Option<String>) -> Result<Option<DateTime<Utc>>, String> {
     match opt {
         Some(value) => DateTime::parse_from_rfc3339(&value)
@@ -36,53 +78,84 @@ fn parse_rfc3339(opt: Option<String>) -> Result<Option<DateTime<Utc>>, String> {
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/surveys",
+    tag = "surveys",
+    request_body = CreateSurveyRequest,
+    responses(
+        (status = 201, description = "Survey record created", body = ApiResponse),
+        (status = 409, description = "A survey with this id already exists"),
+    ),
+    security(("bearer_auth" = [])),
+)]
 pub async fn create_survey_handler(
-    headers: HeaderMap,
+    role: auth::RequireRole<auth::Surveyor>,
     State(state): State<AppState>,
     Json(payload): Json<CreateSurveyRequest>,
-) -> Response {
-    if let Err(err) = auth::claims_from_headers(&headers) {
-        return err.into_response();
-    }
+) -> Result<Response, AppError> {
+    let id = database::create_survey_record(&state.db, payload, &role.claims.account_id).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse {
+            success: true,
+            message: "Survey record created".to_string(),
+            internal_id: Some(id),
+        }),
+    )
+        .into_response())
+}
 
-    match database::create_survey_record(&state.db, payload).await {
-        Ok(id) => (
-            StatusCode::CREATED,
-            Json(ApiResponse {
-                success: true,
-                message: "Survey record created".to_string(),
-                internal_id: Some(id),
-            }),
-        )
-            .into_response(),
-        Err(e) => {
-            tracing::error!("Failed to create survey: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse {
-                    success: false,
-                    message: format!("Failed to create record: {}", e),
-                    internal_id: None,
-                }),
-            )
-                .into_response()
-        }
+/// Field devices sync many surveys at once after being offline; insert them
+/// all in one round trip and report which ones landed.
+pub async fn create_surveys_batch_handler(
+    role: auth::RequireRole<auth::Surveyor>,
+    State(state): State<AppState>,
+    Json(payload): Json<Vec<CreateSurveyRequest>>,
+) -> Result<Response, AppError> {
+    if payload.is_empty() {
+        return Err(AppError::BadRequest(
+            "request body must contain at least one survey".to_string(),
+        ));
     }
+
+    let results =
+        database::create_survey_records(&state.db, payload, &role.claims.account_id).await?;
+    Ok((StatusCode::OK, Json(results)).into_response())
+}
+
+pub async fn get_surveys_batch_handler(
+    role: auth::RequireRole<auth::Viewer>,
+    State(state): State<AppState>,
+    Json(payload): Json<BatchGetRequest>,
+) -> Result<Response, AppError> {
+    let owner = (role.claims.role < auth::Role::Admin).then_some(role.claims.account_id.as_str());
+
+    let records = database::get_surveys(&state.db, &payload.ids, owner).await?;
+    Ok((StatusCode::OK, Json(records)).into_response())
 }
 
 pub async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, "OK")
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/surveys/upload-url",
+    tag = "surveys",
+    request_body = PresignUploadRequest,
+    responses(
+        (status = 200, description = "Presigned PUT URL issued", body = PresignUploadResponse),
+        (status = 400, description = "Missing survey_id/filename"),
+        (status = 404, description = "Survey not found, or not owned by the caller"),
+    ),
+    security(("bearer_auth" = [])),
+)]
 pub async fn create_upload_url_handler(
-    headers: HeaderMap,
+    role: auth::RequireRole<auth::Surveyor>,
     State(state): State<AppState>,
     Json(payload): Json<PresignUploadRequest>,
 ) -> Response {
-    if let Err(err) = auth::claims_from_headers(&headers) {
-        return err.into_response();
-    }
-
     if payload.survey_id.trim().is_empty() || payload.filename.trim().is_empty() {
         return (
             StatusCode::BAD_REQUEST,
@@ -96,8 +169,8 @@ pub async fn create_upload_url_handler(
     }
 
     match database::get_survey(&state.db, &payload.survey_id).await {
-        Ok(Some(_)) => {}
-        Ok(None) => {
+        Ok(Some(record)) if survey_owned_by(&record, &role.claims) => {}
+        Ok(Some(_)) | Ok(None) => {
             return (StatusCode::NOT_FOUND, "Survey not found").into_response();
         }
         Err(e) => {
@@ -129,14 +202,10 @@ pub async fn create_upload_url_handler(
         .clone()
         .unwrap_or_else(|| "application/octet-stream".to_string());
 
-    let upload_url = match storage::presign_put_url(
-        &state.s3_client,
-        &state.bucket_name,
-        &file_key,
-        Some(&content_type),
-        expires_in,
-    )
-    .await
+    let upload_url = match state
+        .object_store
+        .presign_put(&file_key, Some(&content_type), expires_in)
+        .await
     {
         Ok(url) => url,
         Err(e) => {
@@ -162,15 +231,112 @@ pub async fn create_upload_url_handler(
     (StatusCode::OK, Json(response)).into_response()
 }
 
-pub async fn complete_upload_handler(
-    headers: HeaderMap,
+/// Browser direct-upload via a signed POST-object policy. Unlike the
+/// presigned-PUT route, this lets the browser do a plain multipart `<form>`
+/// POST with an enforced max file size and allowed MIME prefix baked into
+/// the policy conditions, instead of having to match an exact content-type.
+pub async fn create_upload_post_handler(
+    role: auth::RequireRole<auth::Surveyor>,
     State(state): State<AppState>,
-    Json(payload): Json<CompleteUploadRequest>,
+    Json(payload): Json<PostUploadRequest>,
 ) -> Response {
-    if let Err(err) = auth::claims_from_headers(&headers) {
-        return err.into_response();
+    if payload.survey_id.trim().is_empty() || payload.filename.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse {
+                success: false,
+                message: "survey_id and filename are required".to_string(),
+                internal_id: None,
+            }),
+        )
+            .into_response();
+    }
+
+    match database::get_survey(&state.db, &payload.survey_id).await {
+        Ok(Some(record)) if survey_owned_by(&record, &role.claims) => {}
+        Ok(Some(_)) | Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Survey not found").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to check survey: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to check survey",
+            )
+                .into_response();
+        }
     }
 
+    let ext = FsPath::new(&payload.filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_default();
+
+    let file_key = format!(
+        "surveys/{}/{}{}",
+        payload.survey_id,
+        uuid::Uuid::new_v4(),
+        ext
+    );
+
+    let expires_in = payload.expires_in.unwrap_or(900).clamp(60, 3600);
+    let content_type_prefix = payload
+        .content_type_prefix
+        .clone()
+        .unwrap_or_else(|| "image/".to_string());
+    let max_bytes = payload.max_bytes.unwrap_or(25 * 1024 * 1024).min(100 * 1024 * 1024);
+
+    let policy = match state
+        .object_store
+        .presign_post(&file_key, &content_type_prefix, max_bytes, expires_in)
+        .await
+    {
+        Ok(policy) => policy,
+        Err(e) => {
+            tracing::error!("Failed to create POST upload policy: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create upload policy",
+            )
+                .into_response();
+        }
+    };
+
+    // Rewrite the internal MinIO/S3 endpoint to the public-facing one, same
+    // as the presigned-PUT route does for its upload_url.
+    let upload_url = storage::rewrite_presigned_url(&policy.url).unwrap_or(policy.url);
+
+    let response = PostUploadResponse {
+        upload_url,
+        file_key,
+        fields: policy
+            .fields
+            .into_iter()
+            .map(|(name, value)| PresignHeader { name, value })
+            .collect(),
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/surveys/complete",
+    tag = "surveys",
+    request_body = CompleteUploadRequest,
+    responses(
+        (status = 202, description = "Photo queued for processing", body = ApiResponse),
+        (status = 400, description = "Missing survey_id/file_key, or file_key not under the survey"),
+        (status = 404, description = "Survey not found, or not owned by the caller"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn complete_upload_handler(
+    role: auth::RequireRole<auth::Surveyor>,
+    State(state): State<AppState>,
+    Json(payload): Json<CompleteUploadRequest>,
+) -> Response {
     if payload.survey_id.trim().is_empty() || payload.file_key.trim().is_empty() {
         return (
             StatusCode::BAD_REQUEST,
@@ -197,8 +363,8 @@ pub async fn complete_upload_handler(
     }
 
     match database::get_survey(&state.db, &payload.survey_id).await {
-        Ok(Some(_)) => {}
-        Ok(None) => {
+        Ok(Some(record)) if survey_owned_by(&record, &role.claims) => {}
+        Ok(Some(_)) | Ok(None) => {
             return (StatusCode::NOT_FOUND, "Survey not found").into_response();
         }
         Err(e) => {
@@ -211,9 +377,18 @@ pub async fn complete_upload_handler(
         }
     }
 
-    let url = storage::build_object_url(&state.bucket_name, &payload.file_key);
-    if let Err(e) = database::add_photo_url(&state.db, &payload.survey_id, &url).await {
-        tracing::error!("Failed to update DB for photo: {:?}", e);
+    // The client already PUT the raw bytes to `file_key`; the worker
+    // re-encodes in place, so raw_key and dest_key are the same object.
+    if let Err(e) = database::enqueue_photo_job(
+        &state.db,
+        &payload.survey_id,
+        &payload.file_key,
+        &payload.file_key,
+        &payload.file_key,
+    )
+    .await
+    {
+        tracing::error!("Failed to enqueue photo job: {:?}", e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ApiResponse {
@@ -226,66 +401,392 @@ pub async fn complete_upload_handler(
     }
 
     (
-        StatusCode::OK,
+        StatusCode::ACCEPTED,
         Json(ApiResponse {
             success: true,
-            message: "Photo upload completed".to_string(),
+            message: "Photo queued for processing".to_string(),
             internal_id: Some(payload.survey_id),
         }),
     )
         .into_response()
 }
 
-pub async fn get_survey_handler(
+/// Direct multipart upload: the client streams the file straight to us
+/// instead of doing the presigned-PUT + complete round trip. Rejects
+/// non-image content types up front; the background job queue then
+/// validates/re-encodes the bytes and generates the same thumb/medium
+/// variants as the presigned flow, storing everything under
+/// `photos/{photo_id}` so `GET /api/photos/{id}` can serve it back. The
+/// survey's `photo_urls` records the photo id, not a bucket URL.
+pub async fn upload_photo_handler(
+    role: auth::RequireRole<auth::Surveyor>,
     State(state): State<AppState>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    match database::get_survey(&state.db, &id).await {
-        Ok(Some(record)) => (StatusCode::OK, Json(record)).into_response(),
-        Ok(None) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
-        Err(e) => {
-            tracing::error!("Failed to get survey: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to fetch record",
-            )
-                .into_response()
+    Path(survey_id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    let survey = database::get_survey(&state.db, &survey_id).await?;
+    match &survey {
+        Some(record) if survey_owned_by(record, &role.claims) => {}
+        _ => return Err(AppError::NotFound("Survey not found".to_string())),
+    }
+
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to read multipart body: {:?}", e);
+            AppError::BadRequest("Invalid multipart body".to_string())
+        })?
+        .ok_or_else(|| AppError::BadRequest("Missing file part".to_string()))?;
+
+    let content_type = field.content_type().unwrap_or_default().to_string();
+    if !content_type.starts_with("image/") {
+        return Err(AppError::UnprocessableEntity(
+            "Uploaded file must be an image".to_string(),
+        ));
+    }
+
+    let raw_bytes = field
+        .bytes()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to read uploaded file: {:?}", e);
+            AppError::BadRequest("Failed to read uploaded file".to_string())
+        })?
+        .to_vec();
+
+    let photo_id = uuid::Uuid::new_v4().to_string();
+    let raw_key = format!("photos/{}/raw", photo_id);
+    let dest_key = format!("photos/{}", photo_id);
+
+    state
+        .object_store
+        .put(&raw_key, raw_bytes, "application/octet-stream")
+        .await
+        .map_err(AppError::Internal)?;
+
+    database::enqueue_photo_job(&state.db, &survey_id, &photo_id, &raw_key, &dest_key).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ApiResponse {
+            success: true,
+            message: "Photo queued for processing".to_string(),
+            internal_id: Some(photo_id),
+        }),
+    )
+        .into_response())
+}
+
+/// Parses a single-range `Range: bytes=...` header value against an object
+/// of `total_len` bytes, returning the inclusive `(start, end)` byte offsets
+/// to serve. `Err(())` means the range is malformed, multi-range, or
+/// unsatisfiable and the caller should respond `416`.
+fn parse_byte_range(header_value: &str, total_len: u64) -> Result<(u64, u64), ()> {
+    let spec = header_value.strip_prefix("bytes=").ok_or(())?;
+    if spec.contains(',') {
+        // Multiple ranges would require a multipart/byteranges response;
+        // out of scope for serving single photos.
+        return Err(());
+    }
+    let (start_str, end_str) = spec.split_once('-').ok_or(())?;
+
+    if start_str.is_empty() {
+        // Suffix range, e.g. `bytes=-500` for the last 500 bytes.
+        let suffix_len: u64 = end_str.parse().map_err(|_| ())?;
+        if suffix_len == 0 || total_len == 0 {
+            return Err(());
         }
+        let start = total_len.saturating_sub(suffix_len);
+        return Ok((start, total_len - 1));
+    }
+
+    let start: u64 = start_str.parse().map_err(|_| ())?;
+    let end = if end_str.is_empty() {
+        total_len.saturating_sub(1)
+    } else {
+        end_str.parse().map_err(|_| ())?
+    };
+
+    if start >= total_len || start > end {
+        return Err(());
     }
+    Ok((start, end.min(total_len.saturating_sub(1))))
 }
 
-pub async fn list_surveys_handler(
+/// Serves a photo (or one of its variants via `?variant=thumb`) uploaded
+/// through [`upload_photo_handler`], honoring `Range` requests so mobile
+/// clients can resume or partially fetch large images.
+pub async fn get_photo_handler(
     State(state): State<AppState>,
-    Query(params): Query<SurveyQueryParams>,
-) -> impl IntoResponse {
-    let created_from = match parse_rfc3339(params.created_from) {
-        Ok(value) => value,
-        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+    Path(id): Path<String>,
+    Query(params): Query<PhotoQueryParams>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let key = match &params.variant {
+        Some(variant) => format!("photos/{}/{}", id, variant),
+        None => format!("photos/{}", id),
     };
-    let created_to = match parse_rfc3339(params.created_to) {
-        Ok(value) => value,
-        Err(msg) => return (StatusCode::BAD_REQUEST, msg).into_response(),
+
+    let total_len = state.object_store.len(&key).await.map_err(|e| {
+        tracing::warn!("Photo not found for key {}: {:?}", key, e);
+        AppError::NotFound("Photo not found".to_string())
+    })?;
+
+    let range = match headers.get(RANGE).and_then(|v| v.to_str().ok()) {
+        Some(header_value) => match parse_byte_range(header_value, total_len) {
+            Ok(range) => Some(range),
+            Err(()) => {
+                let mut response_headers = HeaderMap::new();
+                response_headers.insert(
+                    CONTENT_RANGE,
+                    HeaderValue::from_str(&format!("bytes */{}", total_len)).unwrap(),
+                );
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    response_headers,
+                    "Requested range not satisfiable",
+                )
+                    .into_response());
+            }
+        },
+        None => None,
     };
 
-    let filters = SurveyQueryFilters {
-        category: params.category,
+    let ranged = state.object_store.get_range(&key, range).await.map_err(|e| {
+        tracing::error!("Failed to read photo for key {}: {:?}", key, e);
+        AppError::Internal(e)
+    })?;
+
+    let content_type = image::guess_format(&ranged.bytes)
+        .ok()
+        .and_then(|fmt| match fmt {
+            image::ImageFormat::Jpeg => Some("image/jpeg"),
+            image::ImageFormat::Png => Some("image/png"),
+            image::ImageFormat::WebP => Some("image/webp"),
+            _ => None,
+        })
+        .unwrap_or("application/octet-stream");
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    Ok(match ranged.range {
+        Some((start, end)) => {
+            response_headers.insert(
+                CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, ranged.total_len))
+                    .unwrap(),
+            );
+            (StatusCode::PARTIAL_CONTENT, response_headers, ranged.bytes).into_response()
+        }
+        None => (StatusCode::OK, response_headers, ranged.bytes).into_response(),
+    })
+}
+
+/// Flips a failed photo job back to `pending` so the worker picks it up
+/// again on its next poll.
+pub async fn retry_photo_job_handler(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Response, AppError> {
+    match database::retry_photo_job(&state.db, &job_id).await? {
+        Some(_) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse {
+                success: true,
+                message: "Photo job queued for retry".to_string(),
+                internal_id: Some(job_id),
+            }),
+        )
+            .into_response()),
+        None => Err(AppError::NotFound(
+            "No failed photo job with that id".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/surveys/{id}",
+    tag = "surveys",
+    params(("id" = String, Path, description = "Survey record id")),
+    responses(
+        (status = 200, description = "Survey record", body = SurveyRecord),
+        (status = 404, description = "Survey not found, or not owned by the caller"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn get_survey_handler(
+    role: auth::RequireRole<auth::Viewer>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let record = database::get_survey(&state.db, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Survey not found".to_string()))?;
+
+    if role.claims.role < auth::Role::Admin
+        && record.created_by.as_deref() != Some(role.claims.account_id.as_str())
+    {
+        return Err(AppError::NotFound("Survey not found".to_string()));
+    }
+
+    Ok((StatusCode::OK, Json(record)).into_response())
+}
+
+fn build_survey_filters(params: SurveyQueryParams) -> Result<SurveyQueryFilters, String> {
+    let created_from = parse_rfc3339(params.created_from)?;
+    let created_to = parse_rfc3339(params.created_to)?;
+    let near = parse_near(params.near)?;
+    let category = params
+        .category
+        .map(|c| {
+            SurveyCategory::parse_token(&c)
+                .map(|cat| cat.as_token().to_string())
+                .ok_or_else(|| format!("Invalid category: {c}"))
+        })
+        .transpose()?;
+
+    Ok(SurveyQueryFilters {
+        category,
         start_point: params.start_point,
         end_point: params.end_point,
         created_from,
         created_to,
+        near,
+        awaiting_only: params.awaiting_only,
+        owner: None,
         limit: params.limit,
         offset: params.offset,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/surveys",
+    tag = "surveys",
+    params(SurveyQueryParams),
+    responses(
+        (status = 200, description = "Page of survey records", body = SurveyListResponse),
+        (status = 400, description = "Invalid filter value"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+pub async fn list_surveys_handler(
+    role: auth::RequireRole<auth::Viewer>,
+    State(state): State<AppState>,
+    Query(params): Query<SurveyQueryParams>,
+) -> Result<Response, AppError> {
+    let mine = params.mine;
+    let mut filters = build_survey_filters(params).map_err(AppError::BadRequest)?;
+    filters.owner = if role.claims.role < auth::Role::Admin || mine {
+        Some(role.claims.account_id.clone())
+    } else {
+        None
     };
 
-    match database::list_surveys(&state.db, filters).await {
-        Ok(records) => (StatusCode::OK, Json(records)).into_response(),
-        Err(e) => {
-            tracing::error!("Failed to list surveys: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to fetch records",
-            )
-                .into_response()
-        }
+    // Same page size `database::list_surveys` applies: 50 when unset or <= 0.
+    let limit = match filters.limit.unwrap_or(50) {
+        l if l <= 0 => 50,
+        l => l.min(200),
+    };
+    let offset = filters.offset.unwrap_or(0).max(0);
+
+    let total = database::count_surveys(&state.db, &filters).await?;
+    let records = database::list_surveys(&state.db, filters).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(SurveyListResponse {
+            records,
+            total,
+            limit,
+            offset,
+        }),
+    )
+        .into_response())
+}
+
+/// Streams all records matching the listing filters as CSV (no
+/// pagination), flattening the nested [`SurveyDetails`] fields and joining
+/// photo ids, for pulling survey campaigns into spreadsheets/GIS tools.
+pub async fn export_surveys_handler(
+    role: auth::RequireRole<auth::Viewer>,
+    State(state): State<AppState>,
+    Query(params): Query<SurveyQueryParams>,
+) -> Result<Response, AppError> {
+    let mine = params.mine;
+    let mut filters = build_survey_filters(params).map_err(AppError::BadRequest)?;
+    filters.owner = if role.claims.role < auth::Role::Admin || mine {
+        Some(role.claims.account_id.clone())
+    } else {
+        None
+    };
+    let records = database::list_surveys_for_export(&state.db, &filters).await?;
+    let csv_body = survey_records_to_csv(&records)?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/csv"));
+    response_headers.insert(
+        axum::http::header::CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"surveys.csv\""),
+    );
+
+    Ok((StatusCode::OK, response_headers, csv_body).into_response())
+}
+
+fn survey_records_to_csv(records: &[crate::models::SurveyRecord]) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record([
+        "id",
+        "start_point",
+        "end_point",
+        "orientation",
+        "distance",
+        "top_distance",
+        "category",
+        "diameter",
+        "length",
+        "width",
+        "protrusion",
+        "siltation_depth",
+        "crossing_pipe_count",
+        "issues",
+        "photo_ids",
+        "awaiting_photo_count",
+        "remarks",
+        "created_at",
+    ])?;
+
+    for record in records {
+        let details = &record.details.0;
+        writer.write_record([
+            record.id.as_str(),
+            record.start_point.as_str(),
+            record.end_point.as_str(),
+            record.orientation.as_str(),
+            record.distance.to_string().as_str(),
+            record.top_distance.as_str(),
+            record.category.as_token(),
+            &details.diameter.map(|v| v.to_string()).unwrap_or_default(),
+            &details.length.map(|v| v.to_string()).unwrap_or_default(),
+            &details.width.map(|v| v.to_string()).unwrap_or_default(),
+            &details.protrusion.map(|v| v.to_string()).unwrap_or_default(),
+            &details.siltation_depth.map(|v| v.to_string()).unwrap_or_default(),
+            &details.crossing_pipe_count.map(|v| v.to_string()).unwrap_or_default(),
+            &details.issues.clone().unwrap_or_default().join(";"),
+            &record.photo_urls.join(";"),
+            record.awaiting_photo_count.to_string().as_str(),
+            record.remarks.clone().unwrap_or_default().as_str(),
+            &record
+                .created_at
+                .map(|dt| dt.to_rfc3339())
+                .unwrap_or_default(),
+        ])?;
     }
+
+    Ok(writer.into_inner()?)
 }
@@ -0,0 +1,130 @@
+use crate::database::{self, AppState, PhotoJob};
+use crate::media;
+use std::time::Duration;
+
+/// How long the worker sleeps after finding no pending job before polling again.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Spawns the background worker that drains the `photo_jobs` queue. Runs for
+/// the lifetime of the process; a single crashed job is logged and does not
+/// take the loop down with it.
+pub fn spawn_worker(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            match database::claim_next_photo_job(&state.db).await {
+                Ok(Some(job)) => {
+                    let job_id = job.id.clone();
+                    if let Err(e) = run_photo_job(&state, &job).await {
+                        tracing::warn!("Photo job {} failed: {:?}", job_id, e);
+                        if let Err(db_err) =
+                            database::mark_photo_job_failed(&state.db, &job_id, &e.to_string())
+                                .await
+                        {
+                            tracing::error!("Failed to mark photo job {} failed: {:?}", job_id, db_err);
+                        }
+                        if let Err(db_err) =
+                            database::set_photo_status(&state.db, &job.survey_id, &job.photo_ref, "failed")
+                                .await
+                        {
+                            tracing::error!("Failed to set photo status for job {}: {:?}", job_id, db_err);
+                        }
+                        if let Err(db_err) =
+                            database::add_photo_error(&state.db, &job.survey_id, &e.to_string()).await
+                        {
+                            tracing::error!("Failed to record photo error for job {}: {:?}", job_id, db_err);
+                        }
+                    }
+                }
+                Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                Err(e) => {
+                    tracing::error!("Failed to claim photo job: {:?}", e);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    });
+}
+
+/// Validates, re-encodes, and extracts everything from the raw bytes at
+/// `job.raw_key`, storing results under `job.dest_key`, then marks the job
+/// and photo status `ready`.
+async fn run_photo_job(state: &AppState, job: &PhotoJob) -> anyhow::Result<()> {
+    let raw_bytes = state.object_store.get(&job.raw_key).await?;
+
+    let ingested = media::validate_and_reencode(&raw_bytes)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    state
+        .object_store
+        .put(&job.dest_key, ingested.bytes.clone(), ingested.format.content_type())
+        .await?;
+
+    // Multipart uploads live at `photos/{photo_ref}` and are served back by
+    // id through `GET /api/photos/{id}`, so they are recorded by that id;
+    // presigned uploads are recorded by their object URL.
+    let url = if job.dest_key == format!("photos/{}", job.photo_ref) {
+        job.photo_ref.clone()
+    } else {
+        state.object_store.build_object_url(&job.dest_key)
+    };
+
+    let exif = media::parse_exif(&raw_bytes);
+    if exif.lat.is_some() || exif.lon.is_some() || exif.captured_at.is_some() {
+        if let Err(e) = database::set_survey_geo(
+            &state.db,
+            &job.survey_id,
+            exif.lat,
+            exif.lon,
+            exif.captured_at,
+        )
+        .await
+        {
+            tracing::warn!("Failed to record photo EXIF geo for job {}: {:?}", job.id, e);
+        }
+    }
+    if let Err(e) = database::add_photo_exif(&state.db, &job.survey_id, &url, &(&exif).into()).await {
+        tracing::warn!("Failed to record photo EXIF for job {}: {:?}", job.id, e);
+    }
+
+    let blurhash = match media::compute_blurhash(&ingested.bytes, ingested.format) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::warn!("Failed to compute blurhash for job {}: {}", job.id, e);
+            String::new()
+        }
+    };
+    database::add_photo_url(&state.db, &job.survey_id, &url, &blurhash).await?;
+
+    match media::generate_variants(&ingested.bytes, ingested.format) {
+        Ok(variants) => {
+            let mut variant_urls = std::collections::HashMap::new();
+            for (name, bytes) in variants {
+                let variant_key = format!("{}/{}", job.dest_key, name);
+                if let Err(e) = state
+                    .object_store
+                    .put(&variant_key, bytes, ingested.format.content_type())
+                    .await
+                {
+                    tracing::error!("Failed to upload {} variant for job {}: {:?}", name, job.id, e);
+                    continue;
+                }
+                variant_urls.insert(name.to_string(), state.object_store.build_object_url(&variant_key));
+            }
+            if !variant_urls.is_empty() {
+                if let Err(e) =
+                    database::add_photo_variants(&state.db, &job.survey_id, &url, &variant_urls).await
+                {
+                    tracing::error!("Failed to record photo variants for job {}: {:?}", job.id, e);
+                }
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Failed to generate variants for job {}: {}", job.id, e);
+        }
+    }
+
+    database::mark_photo_job_done(&state.db, &job.id).await?;
+    database::set_photo_status(&state.db, &job.survey_id, &job.photo_ref, "ready").await?;
+
+    Ok(())
+}
@@ -1,27 +1,85 @@
+pub mod auth;
 pub mod database;
+pub mod error;
 pub mod handlers;
+pub mod jobs;
+pub mod media;
+pub mod metrics;
 pub mod models;
+pub mod openapi;
 pub mod storage;
-pub mod auth;
 
 use axum::{
+    http::{HeaderValue, Method},
+    middleware,
     routing::{get, post},
     Router,
 };
 use database::AppState;
+use openapi::ApiDoc;
+use std::env;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{Any, CorsLayer};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 use tower_http::trace::TraceLayer;
 
+/// Builds the CORS layer from `CORS_ALLOWED_ORIGINS` (comma-separated list
+/// of origins). Unset/empty defaults to permissive, since that's the right
+/// default for local/dev; production deployments should set it explicitly.
+fn build_cors_layer() -> CorsLayer {
+    let origins: Vec<HeaderValue> = env::var("CORS_ALLOWED_ORIGINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| HeaderValue::from_str(s).ok())
+        .collect();
+
+    if origins.is_empty() {
+        tracing::warn!(
+            "CORS_ALLOWED_ORIGINS not set; allowing any origin (fine for dev, set it in production)"
+        );
+        CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods(Any)
+            .allow_headers(Any)
+    } else {
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers(Any)
+    }
+}
+
 pub fn create_router(state: AppState) -> Router {
     Router::new()
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route("/health", get(handlers::health_check))
+        .route("/metrics", get(metrics::metrics_handler))
         .route("/api/login", post(auth::login))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/logout", post(auth::logout))
         .route("/api/surveys/upload-url", post(handlers::create_upload_url_handler))
+        .route("/api/surveys/upload-post", post(handlers::create_upload_post_handler))
         .route("/api/surveys/complete", post(handlers::complete_upload_handler))
         .route(
             "/api/surveys",
             get(handlers::list_surveys_handler).post(handlers::create_survey_handler),
         )
+        .route("/api/surveys/export", get(handlers::export_surveys_handler))
+        .route("/api/surveys/batch", post(handlers::create_surveys_batch_handler))
+        .route("/api/surveys/batch-get", post(handlers::get_surveys_batch_handler))
         .route("/api/surveys/:id", get(handlers::get_survey_handler))
+        .route("/api/surveys/:id/photos", post(handlers::upload_photo_handler))
+        .route("/api/photos/:id", get(handlers::get_photo_handler))
+        .route(
+            "/api/photo-jobs/:id/retry",
+            post(handlers::retry_photo_job_handler),
+        )
+        .layer(middleware::from_fn(metrics::track_metrics))
         .layer(TraceLayer::new_for_http())
+        .layer(CompressionLayer::new())
+        .layer(build_cors_layer())
         .with_state(state)
 }
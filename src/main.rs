@@ -1,16 +1,7 @@
-use axum::{
-    routing::{get, post},
-    Router,
-};
 use dotenvy::dotenv;
+use ifem_radar_v2::{create_router, database};
 use std::env;
 use std::net::SocketAddr;
-use tower_http::trace::TraceLayer;
-
-mod database;
-mod handlers;
-mod models;
-mod storage;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -24,28 +15,23 @@ async fn main() -> anyhow::Result<()> {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = database::connect_db(&database_url).await?;
 
-    let s3_client = storage::init_s3_client().await;
     let bucket_name = env::var("AWS_BUCKET_NAME").unwrap_or_else(|_| "ifem-radar".to_string());
+    let object_store = ifem_radar_v2::storage::init_object_store(&bucket_name).await;
 
     // Run migrations
     sqlx::migrate!("./migrations").run(&pool).await?;
 
     let app_state = database::AppState {
         db: pool,
-        s3_client,
+        object_store,
         bucket_name,
     };
 
+    // Background photo-processing worker
+    ifem_radar_v2::jobs::spawn_worker(app_state.clone());
+
     // Router
-    let app = Router::new()
-        .route("/health", get(handlers::health_check))
-        .route("/api/surveys", post(handlers::create_survey_handler))
-        .route(
-            "/api/surveys/:id/photos",
-            post(handlers::upload_photo_handler),
-        )
-        .layer(TraceLayer::new_for_http())
-        .with_state(app_state);
+    let app = create_router(app_state);
 
     // Run
     let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
@@ -0,0 +1,267 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use image::{GenericImageView, ImageFormat as CrateFormat};
+use std::io::Cursor;
+
+/// Images larger than this (longest edge, in pixels) are rejected outright.
+pub const MAX_DIMENSION_PX: u32 = 8000;
+/// Uploaded objects larger than this are rejected before we even try to decode them.
+pub const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    WebP,
+}
+
+impl ImageFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::WebP => "image/webp",
+        }
+    }
+
+    fn to_crate_format(self) -> CrateFormat {
+        match self {
+            ImageFormat::Jpeg => CrateFormat::Jpeg,
+            ImageFormat::Png => CrateFormat::Png,
+            ImageFormat::WebP => CrateFormat::WebP,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MediaError {
+    UnsupportedFormat,
+    Corrupt,
+    ExceedsLimits(String),
+}
+
+impl std::fmt::Display for MediaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaError::UnsupportedFormat => {
+                write!(f, "unsupported image format (only JPEG/PNG/WebP allowed)")
+            }
+            MediaError::Corrupt => write!(f, "image data is corrupt or could not be decoded"),
+            MediaError::ExceedsLimits(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl std::error::Error for MediaError {}
+
+/// A validated, re-encoded image ready to be persisted. Re-encoding drops
+/// EXIF/XMP metadata embedded in the original bytes.
+pub struct IngestedImage {
+    pub format: ImageFormat,
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A named downscaled derivative generated from every ingested photo.
+pub struct Variant {
+    pub name: &'static str,
+    pub max_dimension: u32,
+}
+
+/// Survey listing UIs load many photos at once; these are the sizes we
+/// generate eagerly so the API can serve the right one per client.
+pub const VARIANTS: &[Variant] = &[
+    Variant {
+        name: "thumb",
+        max_dimension: 256,
+    },
+    Variant {
+        name: "medium",
+        max_dimension: 1024,
+    },
+];
+
+/// Re-decodes the (already-validated, metadata-stripped) bytes and produces
+/// one resized, re-encoded derivative per entry in [`VARIANTS`].
+pub fn generate_variants(
+    bytes: &[u8],
+    format: ImageFormat,
+) -> Result<Vec<(&'static str, Vec<u8>)>, MediaError> {
+    let img = image::load_from_memory_with_format(bytes, format.to_crate_format())
+        .map_err(|_| MediaError::Corrupt)?;
+
+    let mut variants = Vec::with_capacity(VARIANTS.len());
+    for variant in VARIANTS {
+        let (width, height) = img.dimensions();
+        let longest_edge = width.max(height);
+        let resized = if longest_edge <= variant.max_dimension {
+            img.clone()
+        } else {
+            img.resize(
+                variant.max_dimension,
+                variant.max_dimension,
+                image::imageops::FilterType::Lanczos3,
+            )
+        };
+
+        let mut buf = Vec::new();
+        resized
+            .write_to(&mut Cursor::new(&mut buf), format.to_crate_format())
+            .map_err(|_| MediaError::Corrupt)?;
+        variants.push((variant.name, buf));
+    }
+
+    Ok(variants)
+}
+
+/// Computes a BlurHash placeholder so clients can render an instant blurred
+/// preview while the full image loads. Downscales first since BlurHash only
+/// needs a handful of DCT-like components, not the full-resolution pixels.
+pub fn compute_blurhash(bytes: &[u8], format: ImageFormat) -> Result<String, MediaError> {
+    let img = image::load_from_memory_with_format(bytes, format.to_crate_format())
+        .map_err(|_| MediaError::Corrupt)?;
+    let working = img.thumbnail(100, 100).to_rgba8();
+    blurhash::encode(4, 3, working.width(), working.height(), working.as_raw())
+        .map_err(|_| MediaError::Corrupt)
+}
+
+/// GPS coordinates and capture time recovered from a photo's EXIF tags, when
+/// present. Extracted from the *original* bytes, since re-encoding (done to
+/// strip metadata before storage) drops this data.
+#[derive(Debug, Default, Clone)]
+pub struct PhotoExif {
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub captured_at: Option<DateTime<Utc>>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+}
+
+impl From<&PhotoExif> for crate::models::PhotoExifInfo {
+    fn from(exif: &PhotoExif) -> Self {
+        crate::models::PhotoExifInfo {
+            lat: exif.lat,
+            lon: exif.lon,
+            captured_at: exif.captured_at,
+            camera_make: exif.camera_make.clone(),
+            camera_model: exif.camera_model.clone(),
+        }
+    }
+}
+
+fn exif_ascii_field(reader: &exif::Reader, tag: exif::Tag) -> Option<String> {
+    reader
+        .get_field(tag, exif::In::PRIMARY)
+        .and_then(|field| match &field.value {
+            exif::Value::Ascii(ref ascii) => ascii.first(),
+            _ => None,
+        })
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .map(|s| s.trim_end_matches('\0').trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn exif_gps_to_decimal(field: &exif::Field, ref_field: Option<&exif::Field>) -> Option<f64> {
+    let exif::Value::Rational(ref values) = field.value else {
+        return None;
+    };
+    if values.len() != 3 {
+        return None;
+    }
+    let degrees = values[0].to_f64();
+    let minutes = values[1].to_f64();
+    let seconds = values[2].to_f64();
+    let mut decimal = degrees + minutes / 60.0 + seconds / 3600.0;
+
+    if let Some(ref_field) = ref_field {
+        if let exif::Value::Ascii(ref ascii) = ref_field.value {
+            if let Some(ref_str) = ascii.first().and_then(|b| std::str::from_utf8(b).ok()) {
+                if ref_str.starts_with('S') || ref_str.starts_with('W') {
+                    decimal = -decimal;
+                }
+            }
+        }
+    }
+
+    Some(decimal)
+}
+
+/// Parses EXIF GPS and `DateTimeOriginal` out of the raw upload, when present.
+pub fn parse_exif(bytes: &[u8]) -> PhotoExif {
+    let mut cursor = Cursor::new(bytes);
+    let reader = match exif::Reader::new().read_from_container(&mut cursor) {
+        Ok(reader) => reader,
+        Err(_) => return PhotoExif::default(),
+    };
+
+    let lat_field = reader.get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY);
+    let lat_ref = reader.get_field(exif::Tag::GPSLatitudeRef, exif::In::PRIMARY);
+    let lon_field = reader.get_field(exif::Tag::GPSLongitude, exif::In::PRIMARY);
+    let lon_ref = reader.get_field(exif::Tag::GPSLongitudeRef, exif::In::PRIMARY);
+
+    let lat = lat_field.and_then(|f| exif_gps_to_decimal(f, lat_ref));
+    let lon = lon_field.and_then(|f| exif_gps_to_decimal(f, lon_ref));
+
+    let captured_at = reader
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .and_then(|field| match &field.value {
+            exif::Value::Ascii(ref ascii) => ascii.first(),
+            _ => None,
+        })
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|s| NaiveDateTime::parse_from_str(s.trim_end_matches('\0'), "%Y:%m:%d %H:%M:%S").ok())
+        .map(|naive| naive.and_utc());
+
+    let camera_make = exif_ascii_field(&reader, exif::Tag::Make);
+    let camera_model = exif_ascii_field(&reader, exif::Tag::Model);
+
+    PhotoExif {
+        lat,
+        lon,
+        captured_at,
+        camera_make,
+        camera_model,
+    }
+}
+
+/// Sniffs the real format from magic bytes, decodes the image, rejects
+/// anything that isn't JPEG/PNG/WebP or exceeds the configured size limits,
+/// then re-encodes to strip metadata.
+pub fn validate_and_reencode(bytes: &[u8]) -> Result<IngestedImage, MediaError> {
+    if bytes.len() > MAX_UPLOAD_BYTES {
+        return Err(MediaError::ExceedsLimits(format!(
+            "file is {} bytes, exceeds the {} byte limit",
+            bytes.len(),
+            MAX_UPLOAD_BYTES
+        )));
+    }
+
+    let crate_format = image::guess_format(bytes).map_err(|_| MediaError::UnsupportedFormat)?;
+    let format = match crate_format {
+        CrateFormat::Jpeg => ImageFormat::Jpeg,
+        CrateFormat::Png => ImageFormat::Png,
+        CrateFormat::WebP => ImageFormat::WebP,
+        _ => return Err(MediaError::UnsupportedFormat),
+    };
+
+    let img = image::load_from_memory_with_format(bytes, crate_format)
+        .map_err(|_| MediaError::Corrupt)?;
+    let (width, height) = img.dimensions();
+    if width.max(height) > MAX_DIMENSION_PX {
+        return Err(MediaError::ExceedsLimits(format!(
+            "image is {}x{}, exceeds the {}px longest-edge limit",
+            width, height, MAX_DIMENSION_PX
+        )));
+    }
+
+    let mut buf = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buf), format.to_crate_format())
+        .map_err(|_| MediaError::Corrupt)?;
+
+    Ok(IngestedImage {
+        format,
+        bytes: buf,
+        width,
+        height,
+    })
+}
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::IntoResponse,
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use once_cell::sync::Lazy;
+use std::time::Instant;
+
+static PROMETHEUS_HANDLE: Lazy<PrometheusHandle> = Lazy::new(|| {
+    PrometheusBuilder::new()
+        .install_recorder()
+        .expect("Failed to install Prometheus recorder")
+});
+
+/// Tower/axum middleware recording a request counter, error counter, and
+/// latency histogram per route + status class, mirroring Garage's `ApiMetrics`.
+pub async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let method = req.method().to_string();
+
+    let start = Instant::now();
+    let response = next.run(req).await;
+    let latency = start.elapsed().as_secs_f64();
+
+    let status = format!("{}xx", response.status().as_u16() / 100);
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", status.clone()),
+    ];
+
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    if response.status().is_server_error() || response.status().is_client_error() {
+        metrics::counter!("http_requests_errors_total", &labels).increment(1);
+    }
+    metrics::histogram!("http_request_duration_seconds", &labels).record(latency);
+
+    response
+}
+
+/// Exposes the accumulated metrics in Prometheus text format.
+pub async fn metrics_handler() -> impl IntoResponse {
+    PROMETHEUS_HANDLE.render()
+}
@@ -1,8 +1,24 @@
 use serde::{Deserialize, Serialize};
 use sqlx::types::Json;
 use sqlx::FromRow;
+use std::collections::HashMap;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+/// variant name (e.g. "thumb", "medium") -> object URL
+pub type PhotoVariantMap = HashMap<String, String>;
+
+/// Per-photo EXIF facts kept in Postgres once extracted; the source bytes
+/// are re-encoded without this metadata before being stored/served.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct PhotoExifInfo {
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub captured_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct SurveyRecord {
     pub id: String, // UUID
     pub start_point: String,
@@ -12,17 +28,39 @@ pub struct SurveyRecord {
     pub top_distance: String, // 頂距 (例如: >0)
 
     pub category: SurveyCategory, // ex. 連接管、橫越館等
+    #[schema(value_type = SurveyDetails)]
     pub details: Json<SurveyDetails>,
 
-    pub photo_urls: Vec<String>,   // 存放在 MinIO 的路徑
+    pub photo_urls: Vec<String>,      // 存放在 MinIO 的路徑
+    pub photo_blurhashes: Vec<String>, // 對應每張照片的 BlurHash placeholder
+    pub photo_errors: Vec<String>, // 驗證失敗的照片錯誤訊息
+    // photo_urls entry -> { variant name -> url }
+    #[schema(value_type = Object)]
+    pub photo_variants: Json<HashMap<String, PhotoVariantMap>>,
+    // photo_urls entry -> EXIF facts extracted from that photo
+    #[schema(value_type = Object)]
+    pub photo_exif: Json<HashMap<String, PhotoExifInfo>>,
+    // photo id (multipart upload) or file_key (presigned upload) -> ingest
+    // status ("pending" | "ready" | "failed")
+    #[schema(value_type = Object)]
+    pub photo_statuses: Json<HashMap<String, String>>,
     pub awaiting_photo_count: i32, // 剩餘待上傳照片張數
     pub remarks: Option<String>,   // 備註
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    // Auto-filled from the first uploaded photo that carries EXIF GPS/time.
+    pub geo_lat: Option<f64>,
+    pub geo_lon: Option<f64>,
+    pub captured_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// `account_info.id` of the surveyor who created this record; `None`
+    /// for rows created before ownership tracking existed. Drives the
+    /// visibility scope in `database::list_surveys` and the inline
+    /// ownership check in `handlers::get_survey_handler`.
+    pub created_by: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone)]
-#[sqlx(type_name = "varchar")]
-#[sqlx(rename_all = "snake_case")]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub enum SurveyCategory {
     ConnectingPipe, // 連接管
     CrossingPipe,   // 橫越管
@@ -36,38 +74,129 @@ pub enum SurveyCategory {
     Unknown,
 }
 
-// Manual implementation for VARCHAR compatibility if needed,
-// using sqlx::Type's built-in support for enums mapped to strings usually works
-// if the DB type is created or if mapped to text.
-// For simplicity in this `investigate.sql` (where category is VARCHAR(50)),
-// we might need to implement Type<Postgres> manually or align names.
-// Here we use `sqlx(rename_all = ...)` to match the lowercase strings in DB if we inserted them that way.
-// However, `investigate.sql` defines it as VARCHAR(50), not a custom ENUM type.
-// So we should treat it as String in DB but Enum in Rust.
-// sqlx `type_name = "varchar"` helps? It might need `sqlx::Type` implementation to proxy to String.
-// Easier way for VARCHAR column: Implement Type by deriving it but saying it is transparent to String?
-// Or just let it be String in struct and convert.
-// Let's try the `sqlx::Type` derive with `#[sqlx(transparent)]` if it was a wrapper, but for enum:
-// We will treat it as String in the Struct for safety, or implement From/To String.
-// For now, let's stick to the user's `spec.rust` intention.
-// User used `#[sqlx(type_name = "varchar")]`. This usually implies a custom type in Postgres,
-// OR we rely on sqlx to handle string conversion.
-// If the column is just VARCHAR, sqlx might complain if we try to bind a custom enum.
-// Let's change `category` in `SurveyRecord` to `String` to be safe, or keep `SurveyCategory` but handle deserialization.
-// Given the user's spec, I'll keep `SurveyCategory` but ensure it works with VARCHAR.
-// Using `sqlx::encode::MakeArg` etc is complex.
-// HACK: I will change the struct field to String for DB storage ease in `database.rs`,
-// but the DTO used for API interaction can use the Enum.
-// ACTUALLY, checking `spec.rust`:
-// ```rust
-// #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
-// #[sqlx(type_name = "varchar")]
-// pub enum SurveyCategory ...
-// ```
-// This suggests the user *wants* it to work this way. I will trust sqlx can handle it or I'll add a proper implementation.
-// To use a Rust enum with a VARCHAR column, usually we implement `Type<DB>` returning `VARCHAR`.
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl SurveyCategory {
+    /// Canonical snake_case token this variant is stored/filtered as in the
+    /// plain `VARCHAR(50)` `category` column; the sole source of truth for
+    /// the `Type`/`Encode`/`Decode` impls below.
+    pub fn as_token(&self) -> &'static str {
+        match self {
+            SurveyCategory::ConnectingPipe => "connecting_pipe",
+            SurveyCategory::CrossingPipe => "crossing_pipe",
+            SurveyCategory::BoxDamage => "box_damage",
+            SurveyCategory::AttachmentLoss => "attachment_loss",
+            SurveyCategory::Siltation => "siltation",
+            SurveyCategory::SectionChange => "section_change",
+            SurveyCategory::CannotPass => "cannot_pass",
+            SurveyCategory::Unknown => "unknown",
+        }
+    }
+
+    /// Parses one of the seven real category tokens, plus the PascalCase
+    /// spellings (`"ConnectingPipe"`, ...) that rows written before this
+    /// type existed still have on disk, from the old
+    /// `serde_json::to_string(&req.category)` storage path. Deliberately
+    /// does not accept `"unknown"`/`"Unknown"`, since that variant is a
+    /// decode-time fallback for garbage values, not a category a caller
+    /// should filter by.
+    pub fn parse_token(s: &str) -> Option<Self> {
+        Some(match s {
+            "connecting_pipe" | "ConnectingPipe" => SurveyCategory::ConnectingPipe,
+            "crossing_pipe" | "CrossingPipe" => SurveyCategory::CrossingPipe,
+            "box_damage" | "BoxDamage" => SurveyCategory::BoxDamage,
+            "attachment_loss" | "AttachmentLoss" => SurveyCategory::AttachmentLoss,
+            "siltation" | "Siltation" => SurveyCategory::Siltation,
+            "section_change" | "SectionChange" => SurveyCategory::SectionChange,
+            "cannot_pass" | "CannotPass" => SurveyCategory::CannotPass,
+            _ => return None,
+        })
+    }
+}
+
+/// Proxies through `&str`/`String` so `SurveyCategory` can be bound and
+/// fetched directly against the plain `VARCHAR(50)` `category` column,
+/// without a custom Postgres enum type.
+impl sqlx::Type<sqlx::Postgres> for SurveyCategory {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+        <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+impl<'q> sqlx::Encode<'q, sqlx::Postgres> for SurveyCategory {
+    fn encode_by_ref(
+        &self,
+        buf: &mut sqlx::postgres::PgArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <&str as sqlx::Encode<sqlx::Postgres>>::encode(self.as_token(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for SurveyCategory {
+    fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let token = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        Ok(SurveyCategory::parse_token(token).unwrap_or(SurveyCategory::Unknown))
+    }
+}
+
+#[cfg(test)]
+mod survey_category_tests {
+    use super::SurveyCategory;
+
+    #[test]
+    fn token_round_trips_for_every_real_variant() {
+        let variants = [
+            SurveyCategory::ConnectingPipe,
+            SurveyCategory::CrossingPipe,
+            SurveyCategory::BoxDamage,
+            SurveyCategory::AttachmentLoss,
+            SurveyCategory::Siltation,
+            SurveyCategory::SectionChange,
+            SurveyCategory::CannotPass,
+        ];
+        for variant in variants {
+            let token = variant.as_token();
+            let parsed = SurveyCategory::parse_token(token).expect("token should parse back");
+            assert_eq!(parsed.as_token(), token);
+        }
+    }
+
+    #[test]
+    fn unexpected_legacy_string_does_not_parse_as_a_real_variant() {
+        assert!(SurveyCategory::parse_token("legacy_unmapped_value").is_none());
+        assert!(SurveyCategory::parse_token("unknown").is_none());
+    }
+
+    /// Rows written before this commit were stored via
+    /// `serde_json::to_string(&req.category)`, which produced the derived
+    /// PascalCase spelling (e.g. `"ConnectingPipe"`), not the snake_case
+    /// `as_token()` form. `Decode::decode` falls through to `parse_token`
+    /// (see the `impl Decode` above), so this exercises the same logic
+    /// `Decode` relies on to avoid silently losing a real category to
+    /// `Unknown` on the first read after upgrade. A live `PgValueRef` needs
+    /// a real connection to construct, so this calls `parse_token` directly
+    /// rather than through the trait.
+    #[test]
+    fn decode_fallback_accepts_legacy_pascal_case_values() {
+        for (legacy, token) in [
+            ("ConnectingPipe", "connecting_pipe"),
+            ("CrossingPipe", "crossing_pipe"),
+            ("BoxDamage", "box_damage"),
+            ("AttachmentLoss", "attachment_loss"),
+            ("Siltation", "siltation"),
+            ("SectionChange", "section_change"),
+            ("CannotPass", "cannot_pass"),
+        ] {
+            let parsed = SurveyCategory::parse_token(legacy)
+                .expect("legacy PascalCase value should still parse");
+            assert_eq!(parsed.as_token(), token);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ChangeOfArea {
     pub width: f64,
     pub height: f64,
@@ -75,7 +204,7 @@ pub struct ChangeOfArea {
     pub change_height: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct SurveyDetails {
     pub diameter: Option<i32>,                // 直徑
     pub length: Option<f64>,                  // 長度 L
@@ -87,14 +216,24 @@ pub struct SurveyDetails {
     pub issues: Option<Vec<String>>,          // 標籤型多選
 }
 
-#[derive(Serialize)]
+/// A page of [`SurveyRecord`]s plus the total count matching the filters,
+/// so the admin UI can render paging controls without a second request.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SurveyListResponse {
+    pub records: Vec<SurveyRecord>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct ApiResponse {
     pub success: bool,
     pub message: String,
     pub internal_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PresignUploadRequest {
     pub survey_id: String,
     pub filename: String,
@@ -102,7 +241,7 @@ pub struct PresignUploadRequest {
     pub expires_in: Option<u64>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PresignUploadResponse {
     pub upload_url: String,
     pub file_key: String,
@@ -110,20 +249,48 @@ pub struct PresignUploadResponse {
     pub required_headers: Vec<PresignHeader>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PresignHeader {
     pub name: String,
     pub value: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct PostUploadRequest {
+    pub survey_id: String,
+    pub filename: String,
+    pub content_type_prefix: Option<String>,
+    pub max_bytes: Option<u64>,
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostUploadResponse {
+    pub upload_url: String,
+    pub file_key: String,
+    pub fields: Vec<PresignHeader>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CompleteUploadRequest {
     pub survey_id: String,
     pub file_key: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchGetRequest {
+    pub ids: Vec<String>,
+}
+
 // Request DTO (what the client sends)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateSurveyRequest {
     pub id: String, // UUID from client
     pub start_point: String,
@@ -0,0 +1,64 @@
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::{auth, handlers, models};
+
+/// Generated OpenAPI document served at `/api-docs/openapi.json`, with a
+/// Swagger UI mounted at `/swagger-ui` in [`crate::create_router`]. Only the
+/// handlers annotated with `#[utoipa::path]` show up here; add new ones to
+/// `paths(...)` as they're annotated.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        auth::login,
+        handlers::create_survey_handler,
+        handlers::list_surveys_handler,
+        handlers::get_survey_handler,
+        handlers::create_upload_url_handler,
+        handlers::complete_upload_handler,
+    ),
+    components(schemas(
+        auth::LoginPayload,
+        auth::AuthBody,
+        models::ApiResponse,
+        models::CreateSurveyRequest,
+        models::SurveyRecord,
+        models::SurveyDetails,
+        models::ChangeOfArea,
+        models::SurveyCategory,
+        models::SurveyListResponse,
+        models::PresignUploadRequest,
+        models::PresignUploadResponse,
+        models::PresignHeader,
+        models::CompleteUploadRequest,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "auth", description = "Login and session management"),
+        (name = "surveys", description = "Survey record creation, listing, and photo uploads"),
+    ),
+)]
+pub struct ApiDoc;
+
+/// Registers the `bearer_auth` scheme referenced by every `security(...)`
+/// attribute above, so Swagger UI renders an "Authorize" button that sends
+/// `Authorization: Bearer <access_token>`.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi
+            .components
+            .as_mut()
+            .expect("ApiDoc declares components(schemas(...))");
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
@@ -1,9 +1,20 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use aws_config::{meta::region::RegionProviderChain, BehaviorVersion};
+use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::{config::Region, Client};
-use aws_sdk_s3::presigning::PresigningConfig;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+type HmacSha256 = Hmac<Sha256>;
 
 fn env_non_empty(key: &str) -> Option<String> {
     std::env::var(key).ok().and_then(|value| {
@@ -20,6 +31,400 @@ fn public_endpoint_url() -> Option<String> {
     env_non_empty("AWS_PUBLIC_ENDPOINT_URL")
 }
 
+/// A signed browser-POST form: the client builds a plain multipart `<form>`
+/// targeting `url` with these `fields` plus a `file` part, and S3/MinIO
+/// enforces the embedded policy conditions (size range, content-type prefix).
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PostPolicy {
+    pub url: String,
+    pub fields: Vec<(String, String)>,
+}
+
+/// A byte range actually returned by [`ObjectStore::get_range`], plus the
+/// total size of the underlying object (needed for the `Content-Range`
+/// header regardless of whether a range was requested).
+pub struct RangedObject {
+    pub bytes: Vec<u8>,
+    pub total_len: u64,
+    /// `Some((start, end))` (inclusive) when a sub-range was served, `None`
+    /// when the whole object was returned.
+    pub range: Option<(u64, u64)>,
+}
+
+/// Backend-agnostic object storage used by `AppState`. Lets handlers stay
+/// oblivious to whether objects live in S3/MinIO or on local disk.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+    async fn head(&self, key: &str) -> Result<bool>;
+    /// Total size in bytes of the stored object.
+    async fn len(&self, key: &str) -> Result<u64>;
+    /// Fetches `range` (inclusive start/end byte offsets), or the whole
+    /// object when `range` is `None`. `range` must already be validated
+    /// against the object's size.
+    async fn get_range(&self, key: &str, range: Option<(u64, u64)>) -> Result<RangedObject>;
+    async fn presign_put(
+        &self,
+        key: &str,
+        content_type: Option<&str>,
+        expires_in_secs: u64,
+    ) -> Result<String>;
+    fn build_object_url(&self, key: &str) -> String;
+
+    /// Signed POST-object policy for direct browser uploads. Backends that
+    /// can't express upload-side conditions (e.g. local fs) fall back to an
+    /// error; callers should treat this as an optional capability.
+    async fn presign_post(
+        &self,
+        _key: &str,
+        _content_type_prefix: &str,
+        _max_bytes: u64,
+        _expires_in_secs: u64,
+    ) -> Result<PostPolicy> {
+        Err(anyhow::anyhow!(
+            "this storage backend does not support POST-policy uploads"
+        ))
+    }
+}
+
+/// S3/MinIO-backed store. Used in production and whenever `STORAGE_BACKEND`
+/// is unset or set to `s3`.
+pub struct S3ObjectStore {
+    client: Client,
+    bucket: String,
+}
+
+impl S3ObjectStore {
+    pub fn new(client: Client, bucket: String) -> Self {
+        Self { client, bucket }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(data))
+            .content_type(content_type)
+            .send()
+            .await
+            .context("Failed to upload to S3")?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let obj = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .context("Failed to get object from S3")?;
+        let bytes = obj
+            .body
+            .collect()
+            .await
+            .context("Failed to read S3 object body")?;
+        Ok(bytes.into_bytes().to_vec())
+    }
+
+    async fn head(&self, key: &str) -> Result<bool> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) if err.as_service_error().map(|e| e.is_not_found()) == Some(true) => {
+                Ok(false)
+            }
+            Err(err) => Err(err).context("Failed to head S3 object"),
+        }
+    }
+
+    async fn len(&self, key: &str) -> Result<u64> {
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .context("Failed to head S3 object")?;
+        Ok(head.content_length().unwrap_or(0).max(0) as u64)
+    }
+
+    async fn get_range(&self, key: &str, range: Option<(u64, u64)>) -> Result<RangedObject> {
+        let mut req = self.client.get_object().bucket(&self.bucket).key(key);
+        if let Some((start, end)) = range {
+            req = req.range(format!("bytes={}-{}", start, end));
+        }
+
+        let obj = req
+            .send()
+            .await
+            .context("Failed to get object from S3")?;
+        let total_len = match &range {
+            // S3 returns `content_range: bytes start-end/total` for ranged
+            // gets; `content_length` on a ranged response is the length of
+            // just the slice, not the whole object.
+            Some(_) => obj
+                .content_range()
+                .and_then(|cr| cr.rsplit_once('/'))
+                .and_then(|(_, total)| total.parse().ok())
+                .unwrap_or(0),
+            None => obj.content_length().unwrap_or(0).max(0) as u64,
+        };
+        let bytes = obj
+            .body
+            .collect()
+            .await
+            .context("Failed to read S3 object body")?
+            .into_bytes()
+            .to_vec();
+
+        Ok(RangedObject {
+            bytes,
+            total_len,
+            range,
+        })
+    }
+
+    async fn presign_put(
+        &self,
+        key: &str,
+        content_type: Option<&str>,
+        expires_in_secs: u64,
+    ) -> Result<String> {
+        let mut req = self.client.put_object().bucket(&self.bucket).key(key);
+        if let Some(content_type) = content_type {
+            req = req.content_type(content_type);
+        }
+
+        let config = PresigningConfig::expires_in(Duration::from_secs(expires_in_secs))?;
+        let presigned = req.presigned(config).await?;
+        Ok(presigned.uri().to_string())
+    }
+
+    fn build_object_url(&self, key: &str) -> String {
+        let endpoint = public_endpoint_url()
+            .or_else(|| env_non_empty("AWS_ENDPOINT_URL"))
+            .unwrap_or_default();
+        if !endpoint.is_empty() {
+            format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, key)
+        } else {
+            format!("s3://{}/{}", self.bucket, key)
+        }
+    }
+
+    async fn presign_post(
+        &self,
+        key: &str,
+        content_type_prefix: &str,
+        max_bytes: u64,
+        expires_in_secs: u64,
+    ) -> Result<PostPolicy> {
+        let creds = self
+            .client
+            .config()
+            .credentials_provider()
+            .ok_or_else(|| anyhow::anyhow!("no credentials provider configured"))?
+            .provide_credentials()
+            .await
+            .context("Failed to resolve AWS credentials")?;
+        let region = self
+            .client
+            .config()
+            .region()
+            .map(|r| r.to_string())
+            .unwrap_or_else(|| "us-east-1".to_string());
+
+        let now = Utc::now();
+        let date_stamp = now.format("%Y%m%d").to_string();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let credential = format!(
+            "{}/{}/{}/s3/aws4_request",
+            creds.access_key_id(),
+            date_stamp,
+            region
+        );
+        let expiration = (now + chrono::Duration::seconds(expires_in_secs as i64))
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+
+        let policy_doc = json!({
+            "expiration": expiration,
+            "conditions": [
+                { "bucket": self.bucket },
+                ["starts-with", "$key", key],
+                ["starts-with", "$Content-Type", content_type_prefix],
+                ["content-length-range", 0, max_bytes],
+                { "x-amz-algorithm": "AWS4-HMAC-SHA256" },
+                { "x-amz-credential": credential },
+                { "x-amz-date": amz_date },
+            ],
+        });
+        let policy_base64 = BASE64.encode(policy_doc.to_string());
+
+        let sign = |key: &[u8], msg: &str| -> Vec<u8> {
+            let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+            mac.update(msg.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        };
+
+        let k_date = sign(format!("AWS4{}", creds.secret_access_key()).as_bytes(), &date_stamp);
+        let k_region = sign(&k_date, &region);
+        let k_service = sign(&k_region, "s3");
+        let k_signing = sign(&k_service, "aws4_request");
+        let signature = hex::encode(sign(&k_signing, &policy_base64));
+
+        let mut fields = vec![
+            ("key".to_string(), key.to_string()),
+            ("Content-Type".to_string(), content_type_prefix.to_string()),
+            ("x-amz-algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
+            ("x-amz-credential".to_string(), credential),
+            ("x-amz-date".to_string(), amz_date),
+            ("policy".to_string(), policy_base64),
+            ("x-amz-signature".to_string(), signature),
+        ];
+        if let Some(token) = creds.session_token() {
+            fields.push(("x-amz-security-token".to_string(), token.to_string()));
+        }
+
+        Ok(PostPolicy {
+            url: build_bucket_url(&self.bucket),
+            fields,
+        })
+    }
+}
+
+fn build_bucket_url(bucket: &str) -> String {
+    let endpoint = public_endpoint_url()
+        .or_else(|| env_non_empty("AWS_ENDPOINT_URL"))
+        .unwrap_or_else(|| "https://s3.amazonaws.com".to_string());
+    format!("{}/{}", endpoint.trim_end_matches('/'), bucket)
+}
+
+/// Local-filesystem store for offline/dev use. Objects are written under
+/// `root/<key>`; "presigning" just hands back a `{base_url}/local-upload/{key}`
+/// URL since there's no real signing involved. `create_router` registers no
+/// `/local-upload` route, so nothing in this crate accepts uploads at it.
+pub struct FsObjectStore {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl FsObjectStore {
+    pub fn new(root: PathBuf, base_url: String) -> Self {
+        Self { root, base_url }
+    }
+
+    /// Joins `key` onto `root`, rejecting any component that isn't a plain
+    /// path segment (`..`, a root/prefix, or `.`) so a client-controlled
+    /// key (e.g. `survey_id`, never validated as a real UUID) can't escape
+    /// `root` via path traversal.
+    fn path_for(&self, key: &str) -> Result<PathBuf> {
+        use std::path::Component;
+
+        let candidate = std::path::Path::new(key);
+        if !candidate
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            anyhow::bail!("invalid object key: {}", key);
+        }
+        Ok(self.root.join(candidate))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for FsObjectStore {
+    async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(&path).await?;
+        file.write_all(&data).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.path_for(key)?;
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read local object {}", path.display()))
+    }
+
+    async fn head(&self, key: &str) -> Result<bool> {
+        Ok(tokio::fs::try_exists(self.path_for(key)?).await?)
+    }
+
+    async fn len(&self, key: &str) -> Result<u64> {
+        let path = self.path_for(key)?;
+        let metadata = tokio::fs::metadata(&path)
+            .await
+            .with_context(|| format!("Failed to stat local object {}", path.display()))?;
+        Ok(metadata.len())
+    }
+
+    async fn get_range(&self, key: &str, range: Option<(u64, u64)>) -> Result<RangedObject> {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        let path = self.path_for(key)?;
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .with_context(|| format!("Failed to read local object {}", path.display()))?;
+        let total_len = file.metadata().await?.len();
+
+        let bytes = match range {
+            Some((start, end)) => {
+                let len = (end - start + 1) as usize;
+                let mut buf = vec![0u8; len];
+                file.seek(std::io::SeekFrom::Start(start)).await?;
+                file.read_exact(&mut buf).await?;
+                buf
+            }
+            None => {
+                let mut buf = Vec::new();
+                file.read_to_end(&mut buf).await?;
+                buf
+            }
+        };
+
+        Ok(RangedObject {
+            bytes,
+            total_len,
+            range,
+        })
+    }
+
+    async fn presign_put(
+        &self,
+        key: &str,
+        _content_type: Option<&str>,
+        _expires_in_secs: u64,
+    ) -> Result<String> {
+        Ok(format!(
+            "{}/local-upload/{}",
+            self.base_url.trim_end_matches('/'),
+            key
+        ))
+    }
+
+    fn build_object_url(&self, key: &str) -> String {
+        format!("{}/local-upload/{}", self.base_url.trim_end_matches('/'), key)
+    }
+}
+
 pub async fn init_s3_client() -> Client {
     let region_provider = RegionProviderChain::default_provider().or_else(Region::new("us-east-1"));
     let config = aws_config::defaults(BehaviorVersion::latest())
@@ -27,16 +432,8 @@ pub async fn init_s3_client() -> Client {
         .load()
         .await;
 
-    // If using MinIO, we need to adjust endpoint_url and force_path_style
-    // This usually comes from ENV variables AWS_ENDPOINT_URL.
-    // aws_config automatically picks up standard AWS env vars.
-    // For MinIO specifically, we often need:
-    // AWS_ENDPOINT_URL=http://localhost:9000
-    // AWS_ACCESS_KEY_ID=minioadmin
-    // AWS_SECRET_ACCESS_KEY=minioadmin
-    // AWS_REGION=us-east-1
-
-    // We'll check if we need to enforce path style (common for MinIO).
+    // For MinIO we need force_path_style plus AWS_ENDPOINT_URL, which
+    // aws_config already picks up from the standard env vars.
     let endpoint = std::env::var("AWS_ENDPOINT_URL").unwrap_or_default();
 
     let builder = aws_sdk_s3::config::Builder::from(&config);
@@ -50,38 +447,24 @@ pub async fn init_s3_client() -> Client {
     Client::from_conf(s3_config)
 }
 
-pub async fn upload_file(
-    client: &Client,
-    bucket: &str,
-    key: &str,
-    data: Vec<u8>,
-    content_type: &str,
-) -> Result<String> {
-    client
-        .put_object()
-        .bucket(bucket)
-        .key(key)
-        .body(ByteStream::from(data))
-        .content_type(content_type)
-        .send()
-        .await
-        .context("Failed to upload to S3")?;
-
-    // Return the URL or Key.
-    // Constructing URL depends on setup (public URL vs internal).
-    // For now, return the key or a constructed path.
-    // If endpoint is set, we might prepend it.
-    Ok(build_object_url(bucket, key))
-}
-
-pub fn build_object_url(bucket: &str, key: &str) -> String {
-    let endpoint = public_endpoint_url()
-        .or_else(|| env_non_empty("AWS_ENDPOINT_URL"))
-        .unwrap_or_default();
-    if !endpoint.is_empty() {
-        format!("{}/{}/{}", endpoint.trim_end_matches('/'), bucket, key)
-    } else {
-        format!("s3://{}/{}", bucket, key)
+/// Selects the object storage backend from `STORAGE_BACKEND` (`s3` | `fs`),
+/// defaulting to `s3`. `fs` keeps the crate runnable without any S3
+/// dependency for local/offline testing.
+pub async fn init_object_store(bucket_name: &str) -> Arc<dyn ObjectStore> {
+    match std::env::var("STORAGE_BACKEND").as_deref() {
+        Ok("fs") => {
+            let root = env_non_empty("STORAGE_FS_ROOT")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("./data/objects"));
+            let base_url = env_non_empty("STORAGE_FS_BASE_URL")
+                .unwrap_or_else(|| "http://localhost:8080".to_string());
+            tracing::info!("Using local-filesystem object store at {}", root.display());
+            Arc::new(FsObjectStore::new(root, base_url))
+        }
+        _ => {
+            let client = init_s3_client().await;
+            Arc::new(S3ObjectStore::new(client, bucket_name.to_string()))
+        }
     }
 }
 
@@ -110,20 +493,3 @@ pub fn rewrite_presigned_url(url: &str) -> Result<String> {
         path_and_query
     ))
 }
-
-pub async fn presign_put_url(
-    client: &Client,
-    bucket: &str,
-    key: &str,
-    content_type: Option<&str>,
-    expires_in_secs: u64,
-) -> Result<String> {
-    let mut req = client.put_object().bucket(bucket).key(key);
-    if let Some(content_type) = content_type {
-        req = req.content_type(content_type);
-    }
-
-    let config = PresigningConfig::expires_in(Duration::from_secs(expires_in_secs))?;
-    let presigned = req.presigned(config).await?;
-    Ok(presigned.uri().to_string())
-}
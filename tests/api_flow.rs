@@ -36,6 +36,65 @@ async fn ensure_bucket(
     }
 }
 
+/// Inserts a test account with the given role (`None` leaves the column
+/// unset, which `auth::Role::from_db` treats as `Viewer`) and returns its
+/// plaintext password for logging in.
+async fn insert_test_account(
+    pool: &sqlx::PgPool,
+    account: &str,
+    role: Option<&str>,
+) -> Result<String, anyhow::Error> {
+    let password = "P@ssw0rd!";
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash password")
+        .to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO account_info (id, account, password_hash, role, is_active)
+        VALUES ($1, $2, $3, $4, TRUE)
+        "#,
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(account)
+    .bind(&password_hash)
+    .bind(role)
+    .execute(pool)
+    .await
+    .context("insert test account")?;
+
+    Ok(password.to_string())
+}
+
+async fn login(
+    app: &axum::Router,
+    account: &str,
+    password: &str,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "account": account,
+                        "password": password,
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await?;
+    anyhow::ensure!(response.status() == StatusCode::OK, "login failed");
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok(serde_json::from_slice(&body)?)
+}
+
 fn set_default_env(key: &str, value: &str) {
     if env::var_os(key).is_none() {
         env::set_var(key, value);
@@ -60,12 +119,12 @@ async fn setup() -> database::AppState {
         .await
         .expect("Failed to connect to DB");
 
-    let s3_client = storage::init_s3_client().await;
     let bucket_name = env::var("AWS_BUCKET_NAME").unwrap_or_else(|_| "ifem-radar-test".to_string());
+    let object_store = storage::init_object_store(&bucket_name).await;
 
     database::AppState {
         db: pool,
-        s3_client,
+        object_store,
         bucket_name,
     }
 }
@@ -91,6 +150,7 @@ fn extract_key_from_url(url: &str, bucket: &str) -> Option<String> {
 async fn test_api_flow_happy_path() {
     let state = setup().await;
     let app = create_router(state.clone());
+    let s3_client = storage::init_s3_client().await;
 
     let account = format!("test_{}", uuid::Uuid::new_v4());
     let password = "P@ssw0rd!";
@@ -117,10 +177,18 @@ async fn test_api_flow_happy_path() {
     let mut created_survey_id: Option<String> = None;
 
     let test_result: Result<(), anyhow::Error> = async {
-        ensure_bucket(&state.s3_client, &state.bucket_name)
+        ensure_bucket(&s3_client, &state.bucket_name)
             .await
             .context("ensure bucket")?;
 
+        // The account above has no role (so `Viewer`); creating a survey
+        // needs at least `Surveyor`.
+        sqlx::query("UPDATE account_info SET role = 'surveyor' WHERE account = $1")
+            .bind(&account)
+            .execute(&state.db)
+            .await
+            .context("grant surveyor role")?;
+
         let login_response = app
             .clone()
             .oneshot(
@@ -268,7 +336,7 @@ async fn test_api_flow_happy_path() {
             .await?;
 
         let complete_status = complete_response.status();
-        if complete_status != StatusCode::OK {
+        if complete_status != StatusCode::ACCEPTED {
             let body = complete_response.into_body().collect().await?.to_bytes();
             let body_str = String::from_utf8_lossy(&body);
             return Err(anyhow::anyhow!(
@@ -292,8 +360,7 @@ async fn test_api_flow_happy_path() {
             }
 
             for key in keys {
-                let _ = state
-                    .s3_client
+                let _ = s3_client
                     .delete_object()
                     .bucket(&state.bucket_name)
                     .key(key)
@@ -317,3 +384,175 @@ async fn test_api_flow_happy_path() {
         panic!("api flow test failed: {:#}", err);
     }
 }
+
+#[cfg(feature = "integration")]
+#[tokio::test]
+async fn test_refresh_token_reuse_revokes_session_chain() {
+    let state = setup().await;
+    let app = create_router(state.clone());
+
+    let account = format!("test_{}", uuid::Uuid::new_v4());
+    let password = insert_test_account(&state.db, &account, None)
+        .await
+        .expect("Failed to insert test account");
+
+    let test_result: Result<(), anyhow::Error> = async {
+        let first_login = login(&app, &account, &password).await?;
+        let first_refresh_token = first_login["refresh_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing refresh_token"))?
+            .to_string();
+
+        // Rotate once: the old refresh token is now revoked, a new pair is issued.
+        let rotate_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/auth/refresh")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "refresh_token": first_refresh_token }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await?;
+        anyhow::ensure!(
+            rotate_response.status() == StatusCode::OK,
+            "first refresh should succeed"
+        );
+        let body = rotate_response.into_body().collect().await?.to_bytes();
+        let rotated: serde_json::Value = serde_json::from_slice(&body)?;
+        let rotated_refresh_token = rotated["refresh_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing rotated refresh_token"))?
+            .to_string();
+
+        // Replaying the now-revoked original token looks like theft: it must
+        // be rejected...
+        let reuse_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/auth/refresh")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "refresh_token": first_refresh_token }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await?;
+        anyhow::ensure!(
+            reuse_response.status() == StatusCode::BAD_REQUEST,
+            "replaying a revoked refresh token should be rejected"
+        );
+
+        // ...and must take the rotated (otherwise still-live) session down
+        // with it, since reuse of an old token is treated as account-wide
+        // theft, not just a bad individual token.
+        let rotated_after_theft_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/auth/refresh")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "refresh_token": rotated_refresh_token }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await?;
+        anyhow::ensure!(
+            rotated_after_theft_response.status() == StatusCode::BAD_REQUEST,
+            "the rotated session should also be revoked once reuse is detected"
+        );
+
+        Ok(())
+    }
+    .await;
+
+    let _ = sqlx::query("DELETE FROM account_info WHERE account = $1")
+        .bind(&account)
+        .execute(&state.db)
+        .await;
+
+    if let Err(err) = test_result {
+        panic!("refresh reuse test failed: {:#}", err);
+    }
+}
+
+#[cfg(feature = "integration")]
+#[tokio::test]
+async fn test_viewer_role_gets_forbidden_on_surveyor_route() {
+    let state = setup().await;
+    let app = create_router(state.clone());
+
+    let account = format!("test_{}", uuid::Uuid::new_v4());
+    // No role set, so `auth::Role::from_db` resolves this account to `Viewer`.
+    let password = insert_test_account(&state.db, &account, None)
+        .await
+        .expect("Failed to insert test account");
+
+    let test_result: Result<(), anyhow::Error> = async {
+        let login_body = login(&app, &account, &password).await?;
+        let token = login_body["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing access_token"))?
+            .to_string();
+
+        let payload = CreateSurveyRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            start_point: "A".to_string(),
+            end_point: "B".to_string(),
+            orientation: "Left".to_string(),
+            distance: 10.5,
+            top_distance: ">0".to_string(),
+            category: SurveyCategory::ConnectingPipe,
+            details: SurveyDetails {
+                diameter: Some(100),
+                length: None,
+                width: None,
+                protrusion: None,
+                siltation_depth: None,
+                crossing_pipe_count: None,
+                change_of_area: None,
+                issues: None,
+            },
+            remarks: None,
+            awaiting_photo_count: 1,
+        };
+
+        let create_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/surveys")
+                    .header("authorization", format!("Bearer {}", token))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&payload)?))
+                    .unwrap(),
+            )
+            .await?;
+
+        anyhow::ensure!(
+            create_response.status() == StatusCode::FORBIDDEN,
+            "a Viewer account should be forbidden from creating a survey, got {}",
+            create_response.status()
+        );
+
+        Ok(())
+    }
+    .await;
+
+    let _ = sqlx::query("DELETE FROM account_info WHERE account = $1")
+        .bind(&account)
+        .execute(&state.db)
+        .await;
+
+    if let Err(err) = test_result {
+        panic!("viewer RBAC test failed: {:#}", err);
+    }
+}
@@ -2,6 +2,9 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use anyhow::Context;
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Argon2, PasswordHasher};
 use dotenvy::dotenv;
 use http_body_util::BodyExt; // for collect
 use ifem_radar_v2::models::{CreateSurveyRequest, SurveyCategory, SurveyDetails};
@@ -10,6 +13,66 @@ use sqlx::postgres::PgPoolOptions;
 use std::env;
 use tower::ServiceExt; // for oneshot
 
+/// Inserts a test account with the given role (`None` leaves the column
+/// unset, which `auth::Role::from_db` treats as `Viewer`) and returns its
+/// id/plaintext password for logging in and for `created_by` ownership.
+async fn insert_test_account(
+    pool: &sqlx::PgPool,
+    account: &str,
+    role: Option<&str>,
+) -> Result<(String, String), anyhow::Error> {
+    let password = "P@ssw0rd!";
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Failed to hash password")
+        .to_string();
+    let account_id = uuid::Uuid::new_v4().to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO account_info (id, account, password_hash, role, is_active)
+        VALUES ($1, $2, $3, $4, TRUE)
+        "#,
+    )
+    .bind(&account_id)
+    .bind(account)
+    .bind(&password_hash)
+    .bind(role)
+    .execute(pool)
+    .await
+    .context("insert test account")?;
+
+    Ok((account_id, password.to_string()))
+}
+
+async fn login(
+    app: &axum::Router,
+    account: &str,
+    password: &str,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "account": account,
+                        "password": password,
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await?;
+    anyhow::ensure!(response.status() == StatusCode::OK, "login failed");
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok(serde_json::from_slice(&body)?)
+}
+
 // Helper to setup state for tests
 async fn setup() -> database::AppState {
     dotenv().ok();
@@ -22,12 +85,12 @@ async fn setup() -> database::AppState {
         .await
         .expect("Failed to connect to DB");
 
-    let s3_client = storage::init_s3_client().await;
     let bucket_name = env::var("AWS_BUCKET_NAME").unwrap_or_else(|_| "ifem-radar-test".to_string());
+    let object_store = storage::init_object_store(&bucket_name).await;
 
     database::AppState {
         db: pool,
-        s3_client,
+        object_store,
         bucket_name,
     }
 }
@@ -55,7 +118,19 @@ async fn test_health_check() {
 #[tokio::test]
 async fn test_create_survey() {
     let state = setup().await;
-    let app = create_router(state);
+    let app = create_router(state.clone());
+
+    let account = format!("test-create-survey-{}", uuid::Uuid::new_v4());
+    let (_, password) = insert_test_account(&state.db, &account, Some("surveyor"))
+        .await
+        .expect("Failed to insert test account");
+    let login_body = login(&app, &account, &password)
+        .await
+        .expect("Failed to log in");
+    let token = login_body["access_token"]
+        .as_str()
+        .expect("access_token missing")
+        .to_string();
 
     let survey_id = uuid::Uuid::new_v4().to_string();
 
@@ -87,6 +162,7 @@ async fn test_create_survey() {
                 .method("POST")
                 .uri("/api/surveys")
                 .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(serde_json::to_vec(&payload).unwrap()))
                 .unwrap(),
         )
@@ -108,7 +184,19 @@ async fn test_upload_photo() {
     let state = setup().await;
     let app = create_router(state.clone());
 
-    // 1. Create a survey record first
+    let account = format!("test-upload-photo-{}", uuid::Uuid::new_v4());
+    let (account_id, password) = insert_test_account(&state.db, &account, Some("surveyor"))
+        .await
+        .expect("Failed to insert test account");
+    let login_body = login(&app, &account, &password)
+        .await
+        .expect("Failed to log in");
+    let token = login_body["access_token"]
+        .as_str()
+        .expect("access_token missing")
+        .to_string();
+
+    // 1. Create a survey record first, owned by the account we just logged in as.
     let survey_id = uuid::Uuid::new_v4().to_string();
     let payload = CreateSurveyRequest {
         id: survey_id.clone(),
@@ -132,23 +220,34 @@ async fn test_upload_photo() {
         awaiting_photo_count: 1,
     };
 
-    database::create_survey_record(&state.db, payload)
+    database::create_survey_record(&state.db, payload, &account_id)
         .await
         .expect("Failed to create survey record");
 
-    // 2. Construct Multipart Body
+    // 2. Construct a multipart body carrying a genuinely-decodable PNG; the
+    // handler's content-type check and the background re-encode worker both
+    // reject non-image bytes.
+    let file_content: image::ImageBuffer<image::Rgb<u8>, Vec<u8>> =
+        image::ImageBuffer::from_fn(8, 8, |x, y| image::Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+    let mut file_bytes = Vec::new();
+    file_content
+        .write_to(&mut std::io::Cursor::new(&mut file_bytes), image::ImageFormat::Png)
+        .expect("encode test png");
+
     let boundary = "------------------------14737809831466499882746641449";
-    let file_content = "fake image content";
-    let body = format!(
-        "--{boundary}\r\n\
-         Content-Disposition: form-data; name=\"file\"; filename=\"test_photo.txt\"\r\n\
-         Content-Type: text/plain\r\n\
-         \r\n\
-         {file_content}\r\n\
-         --{boundary}--\r\n",
-        boundary = boundary,
-        file_content = file_content
+    let mut body = Vec::new();
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"test_photo.png\"\r\n\
+             Content-Type: image/png\r\n\
+             \r\n",
+            boundary = boundary
+        )
+        .as_bytes(),
     );
+    body.extend_from_slice(&file_bytes);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n", boundary = boundary).as_bytes());
 
     // 3. Send Request
     let response = app
@@ -160,14 +259,15 @@ async fn test_upload_photo() {
                     "content-type",
                     format!("multipart/form-data; boundary={}", boundary),
                 )
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::from(body))
                 .unwrap(),
         )
         .await
         .unwrap();
 
-    // 4. Verify Response
-    assert_eq!(response.status(), StatusCode::OK);
+    // 4. Verify Response (photo processing is now queued, not inline)
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
 
     // let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
     // let body_json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
@@ -1,8 +1,23 @@
 use dotenvy::dotenv;
 use ifem_radar_v2::storage;
+use image::ImageBuffer;
 use reqwest::Client;
 use serde_json::json;
 use std::env;
+use std::io::Cursor;
+use std::time::Duration;
+
+/// A tiny, genuinely-decodable PNG; the background job queue validates and
+/// re-encodes the uploaded bytes after `/api/surveys/complete`, so a
+/// text/plain body no longer gets through.
+fn sample_png_bytes() -> Vec<u8> {
+    let img: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
+        ImageBuffer::from_fn(8, 8, |x, y| image::Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .expect("encode sample png");
+    bytes
+}
 
 #[tokio::test]
 #[ignore]
@@ -88,8 +103,8 @@ async fn test_deploy_smoke() {
         .bearer_auth(&token)
         .json(&json!({
             "survey_id": survey_id,
-            "filename": "smoke.txt",
-            "content_type": "text/plain"
+            "filename": "smoke.png",
+            "content_type": "image/png"
         }))
         .send()
         .await
@@ -128,7 +143,7 @@ async fn test_deploy_smoke() {
     let put_resp = client
         .put(upload_url)
         .header("Content-Type", content_type)
-        .body("smoke test")
+        .body(sample_png_bytes())
         .send()
         .await
         .expect("presigned upload request failed");
@@ -149,38 +164,52 @@ async fn test_deploy_smoke() {
 
     assert!(complete_resp.status().is_success());
 
-    // 7) Verify upload recorded in survey
-    let survey_resp = client
-        .get(format!("{}/api/surveys/{}", base_url, survey_id))
-        .send()
-        .await
-        .expect("get survey request failed");
-
-    let survey_status = survey_resp.status();
-    if !survey_status.is_success() {
-        let body = survey_resp
-            .text()
+    // 7) Verify upload recorded in survey. The worker processes the queued
+    // job asynchronously, so poll until it shows up instead of checking once.
+    let mut survey_body: serde_json::Value = serde_json::Value::Null;
+    let mut found = false;
+    for _ in 0..30 {
+        let survey_resp = client
+            .get(format!("{}/api/surveys/{}", base_url, survey_id))
+            .bearer_auth(&token)
+            .send()
             .await
-            .unwrap_or_else(|_| "<failed to read body>".to_string());
-        panic!(
-            "get survey failed: status={} body={}",
-            survey_status, body
-        );
+            .expect("get survey request failed");
+
+        let survey_status = survey_resp.status();
+        if !survey_status.is_success() {
+            let body = survey_resp
+                .text()
+                .await
+                .unwrap_or_else(|_| "<failed to read body>".to_string());
+            panic!(
+                "get survey failed: status={} body={}",
+                survey_status, body
+            );
+        }
+
+        survey_body = survey_resp
+            .json()
+            .await
+            .expect("survey response json parse failed");
+        let photo_urls = survey_body["photo_urls"]
+            .as_array()
+            .expect("photo_urls missing or not array");
+        found = photo_urls.iter().any(|item| {
+            item.as_str()
+                .map(|url| url.contains(&file_key))
+                .unwrap_or(false)
+        });
+        if found {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
     }
-
-    let survey_body: serde_json::Value = survey_resp
-        .json()
-        .await
-        .expect("survey response json parse failed");
-    let photo_urls = survey_body["photo_urls"]
-        .as_array()
-        .expect("photo_urls missing or not array");
-    let found = photo_urls.iter().any(|item| {
-        item.as_str()
-            .map(|url| url.contains(&file_key))
-            .unwrap_or(false)
-    });
-    assert!(found, "uploaded file_key not found in photo_urls");
+    assert!(
+        found,
+        "uploaded file_key not found in photo_urls after waiting for the worker: {:?}",
+        survey_body
+    );
 
     // 8) Verify object exists in MinIO/S3
     let bucket = env::var("AWS_BUCKET_NAME").expect("AWS_BUCKET_NAME is required for minio check");
@@ -1,7 +1,23 @@
 use anyhow::Context;
+use image::{GenericImageView, ImageBuffer, Rgb};
 use reqwest::{header::CONTENT_TYPE, multipart, StatusCode};
 use serde_json::json;
 use std::env;
+use std::io::Cursor;
+use std::time::Duration;
+
+/// A tiny, genuinely-decodable PNG; the upload handler rejects non-image
+/// content types and the background job queue validates and re-encodes the
+/// bytes, so smoke-testing with arbitrary bytes (as this test used to) no
+/// longer gets past either.
+fn sample_png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let img: ImageBuffer<Rgb<u8>, Vec<u8>> =
+        ImageBuffer::from_fn(width, height, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+    let mut bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .expect("encode sample png");
+    bytes
+}
 
 fn smoke_base_url() -> String {
     if let Ok(v) = env::var("SMOKE_BASE_URL") {
@@ -109,11 +125,13 @@ async fn test_smoke_flow() {
             ));
         }
 
-        // Step 6: Upload one photo to the survey.
-        let file_content = "smoke photo content";
-        let file_part = multipart::Part::text(file_content.to_string())
-            .file_name("smoke.txt")
-            .mime_str("text/plain")?;
+        // Step 6: Upload one photo to the survey. The handler only does a
+        // synchronous content-type check and queues the rest; actual
+        // re-encoding happens on the background worker.
+        let file_content = sample_png_bytes(16, 16);
+        let file_part = multipart::Part::bytes(file_content.clone())
+            .file_name("smoke.png")
+            .mime_str("image/png")?;
         let form = multipart::Form::new().part("file", file_part);
 
         let upload_response = client
@@ -124,7 +142,7 @@ async fn test_smoke_flow() {
             .await?;
 
         let upload_status = upload_response.status();
-        if upload_status != StatusCode::OK {
+        if upload_status != StatusCode::ACCEPTED {
             let body = upload_response.text().await.unwrap_or_default();
             return Err(anyhow::anyhow!(
                 "upload failed: status={} body={}",
@@ -133,30 +151,47 @@ async fn test_smoke_flow() {
             ));
         }
 
-        // Step 7: Fetch survey and verify stored photo id and awaiting_photo_count == 0.
-        let get_survey_response = client
-            .get(format!("{}/api/surveys/{}", base_url, survey_id))
-            .send()
-            .await?;
+        // Step 7: Poll the survey until the background worker has picked up
+        // and finished the queued job, then verify awaiting_photo_count == 0.
+        let mut survey_json: serde_json::Value = serde_json::Value::Null;
+        let mut awaiting_photo_count = -1;
+        let mut ready = false;
+        for _ in 0..30 {
+            let get_survey_response = client
+                .get(format!("{}/api/surveys/{}", base_url, survey_id))
+                .bearer_auth(&token)
+                .send()
+                .await?;
 
-        let get_survey_status = get_survey_response.status();
-        if get_survey_status != StatusCode::OK {
-            let body = get_survey_response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "get survey failed: status={} body={}",
-                get_survey_status,
-                body
-            ));
-        }
+            let get_survey_status = get_survey_response.status();
+            if get_survey_status != StatusCode::OK {
+                let body = get_survey_response.text().await.unwrap_or_default();
+                return Err(anyhow::anyhow!(
+                    "get survey failed: status={} body={}",
+                    get_survey_status,
+                    body
+                ));
+            }
 
-        let survey_json: serde_json::Value = get_survey_response.json().await?;
-        let awaiting_photo_count = survey_json["awaiting_photo_count"]
-            .as_i64()
-            .ok_or_else(|| anyhow::anyhow!("missing awaiting_photo_count"))?;
+            survey_json = get_survey_response.json().await?;
+            awaiting_photo_count = survey_json["awaiting_photo_count"]
+                .as_i64()
+                .ok_or_else(|| anyhow::anyhow!("missing awaiting_photo_count"))?;
+            // The awaiting slot is consumed at enqueue time; the job is only
+            // finished once the photo's status reads "ready".
+            ready = survey_json["photo_statuses"]
+                .as_object()
+                .map_or(false, |statuses| statuses.values().any(|s| s == "ready"));
+            if awaiting_photo_count == 0 && ready {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
         anyhow::ensure!(
-            awaiting_photo_count == 0,
-            "expected awaiting_photo_count to be 0 after upload, got {}",
-            awaiting_photo_count
+            awaiting_photo_count == 0 && ready,
+            "photo job did not finish processing in time, awaiting_photo_count={}, ready={}",
+            awaiting_photo_count,
+            ready
         );
 
         let photo_urls = survey_json["photo_urls"]
@@ -178,7 +213,7 @@ async fn test_smoke_flow() {
             photo_id
         );
 
-        // Step 8: Fetch photo by id and verify content and content-type.
+        // Step 8: Fetch photo by id and verify it decodes as an image.
         let get_photo_response = client
             .get(format!("{}/api/photos/{}", base_url, photo_id))
             .bearer_auth(&token)
@@ -196,17 +231,22 @@ async fn test_smoke_flow() {
             .headers()
             .get(CONTENT_TYPE)
             .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
+            .unwrap_or("")
+            .to_string();
         anyhow::ensure!(
-            content_type.starts_with("text/plain"),
+            content_type.starts_with("image/"),
             "unexpected content-type: {}",
             content_type
         );
 
         let get_photo_body = get_photo_response.bytes().await?;
+        let decoded = image::load_from_memory(&get_photo_body)
+            .context("stored photo is not a decodable image")?;
         anyhow::ensure!(
-            get_photo_body.as_ref() == file_content.as_bytes(),
-            "photo content mismatch"
+            decoded.width() == 16 && decoded.height() == 16,
+            "stored photo dimensions changed unexpectedly: {}x{}",
+            decoded.width(),
+            decoded.height()
         );
 
         // Step 9: End smoke flow (no direct DB cleanup in remote mode).